@@ -0,0 +1,33 @@
+use chip8_emulator::asm::assemble;
+use chip8_emulator::chip8::Emulator;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const INSTRUCTIONS: usize = 10_000;
+
+// NOTE(panmar): Draws 15-byte sprites across the whole screen, wrapping at the edges
+fn draw_sprite(c: &mut Criterion) {
+    let program = assemble(
+        "
+            LD I, 0x000
+        loop:
+            DRW V0, V1, 15
+            ADD V0, 7
+            ADD V1, 3
+            JP loop
+        ",
+    )
+    .unwrap();
+    let mut emulator = Emulator::new();
+    emulator.load_program_from_data(&program).unwrap();
+
+    let mut group = c.benchmark_group("draw_sprite");
+    group.throughput(Throughput::Elements(INSTRUCTIONS as u64));
+    group.bench_function("run_instructions", |b| {
+        b.iter(|| assert_eq!(emulator.run_instructions(INSTRUCTIONS), INSTRUCTIONS))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, draw_sprite);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+use chip8_emulator::chip8::Emulator;
+use chip8_emulator::platform::HeadlessPlatform;
+
+use std::{env, process::exit};
+
+// NOTE(panmar): Runs a ROM for a fixed number of frames and prints the last one
+// cargo run --example headless [filepath-to-rom] [frames]
+pub fn main() {
+    let mut args = env::args().skip(1);
+    let mut emulator = Emulator::new();
+    let result = match args.next() {
+        Some(rom_path) => emulator.load_program_from_file(&rom_path),
+        #[rustfmt::skip]
+        None => emulator.load_program_from_data(&[
+            0x00, 0xE0, // CLS
+            0x60, 0x0F, // LD V0, 0x0F
+            0xF0, 0x29, // LD F, V0
+            0xD2, 0x2A, // DRW V2, V2, 0xA
+            0x12, 0x08, // JP 0x208
+        ]),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to load program: {}", e);
+        exit(1);
+    }
+    let frame_count = args.next().and_then(|n| n.parse().ok()).unwrap_or(60);
+
+    let mut platform = HeadlessPlatform::new(frame_count);
+    platform.run(&mut emulator);
+
+    let frame = platform.frames().last().unwrap();
+    for y in 0..frame.height() {
+        let row: String = (0..frame.width())
+            .map(|x| if frame.contains(&(x, y)) { '#' } else { '.' })
+            .collect();
+        println!("{}", row);
+    }
+}
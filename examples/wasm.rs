@@ -0,0 +1,49 @@
+// NOTE(panmar): Exposes the emulator to JavaScript through wasm-bindgen
+// cargo build --example wasm --target wasm32-unknown-unknown --no-default-features
+#![cfg(target_arch = "wasm32")]
+
+use chip8_emulator::chip8::Emulator;
+use std::time::Duration;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Chip8 {
+    emulator: Emulator,
+}
+
+#[wasm_bindgen]
+impl Chip8 {
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom: &[u8]) -> Result<Chip8, JsError> {
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(rom)?;
+        Ok(Chip8 { emulator })
+    }
+
+    pub fn step(&mut self, elapsed_ms: f64) {
+        self.emulator
+            .step(Duration::from_secs_f64(elapsed_ms / 1000.0));
+    }
+
+    /// The display packed 8 pixels per byte, see `Emulator::frame_buffer`.
+    pub fn frame_buffer(&self) -> Vec<u8> {
+        self.emulator.frame_buffer()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.emulator.resolution().width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.emulator.resolution().height()
+    }
+
+    /// Bit `k` of `keypad` set means CHIP-8 key `k` is held down.
+    pub fn set_keypad(&mut self, keypad: u16) {
+        self.emulator.set_keypad(keypad);
+    }
+
+    pub fn is_beeping(&self) -> bool {
+        self.emulator.is_beeping()
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::chip8::{Instruction, PROGRAM_ADDRESS};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl AssembleError {
+    fn new(line: usize, message: String) -> AssembleError {
+        AssembleError { line, message }
+    }
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(usize),
+    Value(u16),
+    I,
+    IndirectI,
+    DelayTimer,
+    SoundTimer,
+    Key,
+    Font,
+    BigFont,
+    Bcd,
+    Long,
+}
+
+struct Statement<'a> {
+    line: usize,
+    mnemonic: &'a str,
+    operands: Vec<&'a str>,
+}
+
+/// Assembles CHIP-8 mnemonics, as printed by the disassembler, into a ROM loaded at
+/// the program address. Lines may start with a `label:`, `;` starts a comment and
+/// `db`/`dw` emit raw bytes/words.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = PROGRAM_ADDRESS;
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut text = text.split(';').next().unwrap().trim();
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_identifier(label) {
+                return Err(AssembleError::new(
+                    line,
+                    format!("invalid label '{}'", label),
+                ));
+            }
+            if labels.insert(label, address as u16).is_some() {
+                return Err(AssembleError::new(
+                    line,
+                    format!("duplicate label '{}'", label),
+                ));
+            }
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = match text.split_once(char::is_whitespace) {
+            Some((mnemonic, operands)) => (mnemonic, operands.split(',').map(str::trim).collect()),
+            None => (text, Vec::new()),
+        };
+        address += match mnemonic.to_ascii_uppercase().as_str() {
+            "DB" => operands.len(),
+            "DW" => 2 * operands.len(),
+            _ => 2,
+        };
+        statements.push(Statement {
+            line,
+            mnemonic,
+            operands,
+        });
+    }
+
+    let mut data = Vec::new();
+    for statement in statements {
+        let line = statement.line;
+        let operands = statement
+            .operands
+            .iter()
+            .map(|operand| parse_operand(operand, &labels, line))
+            .collect::<Result<Vec<_>, _>>()?;
+        match statement.mnemonic.to_ascii_uppercase().as_str() {
+            "DB" => {
+                for operand in operands {
+                    data.push(value(operand, 0xFF, line)? as u8);
+                }
+            }
+            "DW" => {
+                for operand in operands {
+                    data.extend(value(operand, 0xFFFF, line)?.to_be_bytes());
+                }
+            }
+            mnemonic => {
+                let instruction = encode(mnemonic, &operands, line)?;
+                data.extend(instruction.to_opcode().to_be_bytes());
+            }
+        }
+    }
+    Ok(data)
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_operand(
+    text: &str,
+    labels: &HashMap<&str, u16>,
+    line: usize,
+) -> Result<Operand, AssembleError> {
+    let operand = match text.to_ascii_uppercase().as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::DelayTimer,
+        "ST" => Operand::SoundTimer,
+        "K" => Operand::Key,
+        "F" => Operand::Font,
+        "HF" => Operand::BigFont,
+        "B" => Operand::Bcd,
+        "LONG" => Operand::Long,
+        upper => {
+            let register = upper
+                .strip_prefix('V')
+                .filter(|digit| digit.len() == 1)
+                .and_then(|digit| usize::from_str_radix(digit, 16).ok());
+            let number = if let Some(hex) = upper.strip_prefix("0X") {
+                u16::from_str_radix(hex, 16).ok()
+            } else if let Some(binary) = upper.strip_prefix("0B") {
+                u16::from_str_radix(binary, 2).ok()
+            } else {
+                upper.parse().ok()
+            };
+
+            if let Some(register) = register {
+                Operand::Register(register)
+            } else if let Some(number) = number {
+                Operand::Value(number)
+            } else if let Some(address) = labels.get(text) {
+                Operand::Value(*address)
+            } else {
+                return Err(AssembleError::new(
+                    line,
+                    format!("unknown operand '{}'", text),
+                ));
+            }
+        }
+    };
+    Ok(operand)
+}
+
+fn value(operand: Operand, max: u16, line: usize) -> Result<u16, AssembleError> {
+    match operand {
+        Operand::Value(value) if value <= max => Ok(value),
+        Operand::Value(value) => Err(AssembleError::new(
+            line,
+            format!("value {:#X} exceeds {:#X}", value, max),
+        )),
+        _ => Err(AssembleError::new(line, "expected a value".to_string())),
+    }
+}
+
+fn encode(mnemonic: &str, operands: &[Operand], line: usize) -> Result<Instruction, AssembleError> {
+    use Instruction::*;
+    use Operand::*;
+
+    let address = |operand| value(operand, 0xFFF, line);
+    let constant = |operand| value(operand, 0xFF, line).map(|v| v as u8);
+    let instruction = match (mnemonic, operands) {
+        ("SYS", [target]) => CallMachineCode {
+            address: address(*target)?,
+        },
+        ("CLS", []) => ClearDisplay,
+        ("RET", []) => Return,
+        ("SCD", [operand]) => ScrollDown {
+            n_rows: value(*operand, 0xF, line)? as usize,
+        },
+        ("SCR", []) => ScrollRight,
+        ("SCL", []) => ScrollLeft,
+        ("LOW", []) => LowResolution,
+        ("HIGH", []) => HighResolution,
+        ("PLANE", [operand]) => SelectPlanes {
+            planes: value(*operand, 0x3, line)? as u8,
+        },
+        ("AUDIO", []) => LoadAudioPattern,
+        ("PITCH", [Register(register)]) => SetPitch {
+            register: *register,
+        },
+        ("SAVE", [Register(register_first), Register(register_last)]) => StoreRegisterRange {
+            register_first: *register_first,
+            register_last: *register_last,
+        },
+        ("LOAD", [Register(register_first), Register(register_last)]) => LoadRegisterRange {
+            register_first: *register_first,
+            register_last: *register_last,
+        },
+        ("JP", [Register(0), target]) => JumpWithV0Offset {
+            address: address(*target)?,
+        },
+        ("JP", [target]) => Jump {
+            address: address(*target)?,
+        },
+        ("CALL", [target]) => Call {
+            address: address(*target)?,
+        },
+        ("SE", [Register(register_lhs), Register(register_rhs)]) => SkipIfRegEqReg {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("SE", [Register(register), operand]) => SkipIfRegEqConstant {
+            register: *register,
+            constant: constant(*operand)?,
+        },
+        ("SNE", [Register(register_lhs), Register(register_rhs)]) => CondRegNotEqReg {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("SNE", [Register(register), operand]) => SkipIfRegNotEqConstant {
+            register: *register,
+            constant: constant(*operand)?,
+        },
+        ("LD", [Register(register_lhs), Register(register_rhs)]) => SetRegToReg {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("LD", [Register(register), DelayTimer]) => SetRegToDelayTimer {
+            register: *register,
+        },
+        ("LD", [Register(register), Key]) => AwaitAndSetKeyPress {
+            register: *register,
+        },
+        ("LD", [Register(last_register), IndirectI]) => LoadRegisters {
+            last_register: *last_register,
+        },
+        ("LD", [Register(register), operand]) => SetRegToConstant {
+            register: *register,
+            constant: constant(*operand)?,
+        },
+        ("LD", [I, Long]) => SetLongAddress,
+        ("LD", [I, target]) => SetAddress {
+            address: address(*target)?,
+        },
+        ("LD", [DelayTimer, Register(register)]) => SetDelayTimer {
+            register: *register,
+        },
+        ("LD", [SoundTimer, Register(register)]) => SetSoundTimer {
+            register: *register,
+        },
+        ("LD", [Font, Register(register)]) => SetAddressOfFontChar {
+            register: *register,
+        },
+        ("LD", [BigFont, Register(register)]) => SetAddressOfBigFontChar {
+            register: *register,
+        },
+        ("LD", [Bcd, Register(register)]) => StoreRegBcd {
+            register: *register,
+        },
+        ("LD", [IndirectI, Register(last_register)]) => StoreRegisters {
+            last_register: *last_register,
+        },
+        ("ADD", [I, Register(register)]) => AddRegToAddressWithoutCarry {
+            register: *register,
+        },
+        ("ADD", [Register(register_lhs), Register(register_rhs)]) => AddRegToReg {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("ADD", [Register(register), operand]) => AddConstToReg {
+            register: *register,
+            constant: constant(*operand)?,
+        },
+        ("OR", [Register(register_lhs), Register(register_rhs)]) => BitwiseOr {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("AND", [Register(register_lhs), Register(register_rhs)]) => BitwiseAnd {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("XOR", [Register(register_lhs), Register(register_rhs)]) => BitwiseXor {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("SUB", [Register(register_lhs), Register(register_rhs)]) => SubReg2FromReg1 {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("SUBN", [Register(register_lhs), Register(register_rhs)]) => SubReg1FromReg2 {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("SHR", [Register(register)]) => BitwiseShrBy1 {
+            register_lhs: *register,
+            register_rhs: *register,
+        },
+        ("SHR", [Register(register_lhs), Register(register_rhs)]) => BitwiseShrBy1 {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("SHL", [Register(register)]) => BitwiseShlBy1 {
+            register_lhs: *register,
+            register_rhs: *register,
+        },
+        ("SHL", [Register(register_lhs), Register(register_rhs)]) => BitwiseShlBy1 {
+            register_lhs: *register_lhs,
+            register_rhs: *register_rhs,
+        },
+        ("RND", [Register(register), operand]) => BitwiseAndWithRand {
+            register: *register,
+            constant: constant(*operand)?,
+        },
+        ("DRW", [Register(register_x), Register(register_y), operand]) => DisplaySprite {
+            register_x: *register_x,
+            register_y: *register_y,
+            n_bytes: value(*operand, 0xF, line)? as usize,
+        },
+        ("SKP", [Register(register)]) => SkipIfKeyPressed {
+            register: *register,
+        },
+        ("SKNP", [Register(register)]) => SkipIfKeyNotPressed {
+            register: *register,
+        },
+        _ => {
+            return Err(AssembleError::new(
+                line,
+                format!(
+                    "invalid instruction '{}' with {} operand(s)",
+                    mnemonic,
+                    operands.len()
+                ),
+            ))
+        }
+    };
+    Ok(instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::Emulator;
+    use crate::disasm::disassemble;
+    use std::time::Duration;
+
+    #[test]
+    fn should_assemble_program_that_runs() {
+        // Given
+        let source = "
+            start:
+                LD V0, 0x05
+                CALL double     ; V0 = 10
+                LD I, sprite
+                JP end
+            double:
+                ADD V0, V0
+                RET
+            end: JP end
+            sprite:
+                db 0xF0, 0b10010000
+        ";
+
+        // When
+        let data = assemble(source).unwrap();
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&data).unwrap();
+        for _ in 0..10 {
+            emulator.step(Duration::from_millis(2));
+        }
+
+        // Then
+        assert_eq!(data.len(), 16);
+        assert_eq!(emulator.cpu.registers[0], 10);
+        assert_eq!(emulator.cpu.register_i, 0x20E);
+        assert_eq!(emulator.program_counter(), 0x20C);
+        assert_eq!(emulator.memory[0x20E..0x210], [0xF0, 0x90]);
+    }
+
+    #[test]
+    fn should_assemble_disassembled_program() {
+        // Given
+        let data = vec![
+            0x00, 0xE0, 0x60, 0x0F, 0xF0, 0x29, 0xD2, 0x2A, 0xA2, 0x2A, 0xB3, 0x00, 0x8A, 0xB6,
+            0xF5, 0x65, 0xF2, 0x30, 0x00, 0xC3, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFF, 0x00, 0xFE,
+            0xF3, 0x01, 0xF0, 0x00, 0x12, 0x34, 0x52, 0x92, 0x59, 0x23, 0xF0, 0x02, 0xF4, 0x3A,
+            0x01, 0x23, 0xFF, 0xFF,
+        ];
+        let source: Vec<String> = disassemble(&data)
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect();
+
+        // When
+        let assembled = assemble(&source.join("\n")).unwrap();
+
+        // Then
+        assert_eq!(assembled, data);
+    }
+
+    #[test]
+    fn should_report_line_of_invalid_statement() {
+        assert_eq!(
+            assemble("CLS\nJP nowhere"),
+            Err(AssembleError::new(
+                2,
+                "unknown operand 'nowhere'".to_string()
+            ))
+        );
+        assert_eq!(
+            assemble("LD V0, 0x100"),
+            Err(AssembleError::new(
+                1,
+                "value 0x100 exceeds 0xFF".to_string()
+            ))
+        );
+        assert_eq!(
+            assemble("a:\na:"),
+            Err(AssembleError::new(2, "duplicate label 'a'".to_string()))
+        );
+        assert!(assemble("FOO V1").is_err());
+    }
+}
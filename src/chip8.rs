@@ -1,23 +1,149 @@
-use rand::Rng;
-use std::collections::HashSet;
-use std::fs;
+use crate::disasm;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, path::Path};
 
 pub const SCREEN_WIDTH: u32 = 64;
 pub const SCREEN_HEIGHT: u32 = 32;
+pub const HIRES_SCREEN_WIDTH: u32 = 128;
+pub const HIRES_SCREEN_HEIGHT: u32 = 64;
+/// RGBA colors indexed by the plane bits of a pixel: off, first plane only, second
+/// plane only, both planes.
+pub const DEFAULT_RGBA_PALETTE: [[u8; 4]; 4] = [
+    [0, 0, 0, 255],
+    [255, 255, 255, 255],
+    [255, 102, 0, 255],
+    [102, 34, 0, 255],
+];
 
 const MEMORY_SIZE: usize = 4096;
+const XO_CHIP_MEMORY_SIZE: usize = 65536;
+const ADDRESS_MASK: u16 = 0x0FFF;
+pub(crate) const PROGRAM_ADDRESS: usize = 512;
+const TIMER_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+// NOTE(panmar): Time the CPU may fall behind before cycles are dropped, so a stalled
+// host does not try to catch up forever
+const MAX_CPU_CATCH_UP: Duration = Duration::from_millis(250);
+// NOTE(panmar): XO-CHIP pitch 64 plays the audio pattern at 4000 bits per second
+const DEFAULT_PITCH: u8 = 64;
+// NOTE(panmar): Gives up on stepping over a subroutine that never returns, like a
+// main loop entered with CALL
+const MAX_STEP_OVER_INSTRUCTIONS: usize = 1_000_000;
+
+const FONT_ADDRESS: usize = 0x0000;
+const FONT_SPRITE_SIZE: usize = 5;
+
+#[rustfmt::skip]
+const FONT_SPRITES: [u8; 16 * FONT_SPRITE_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // "0"
+    0x20, 0x60, 0x20, 0x20, 0x70, // "1"
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // "2"
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // "3"
+    0x90, 0x90, 0xF0, 0x10, 0x10, // "4"
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // "5"
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // "6"
+    0xF0, 0x10, 0x20, 0x40, 0x40, // "7"
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // "8"
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // "9"
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // "A"
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // "B"
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // "C"
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // "D"
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // "E"
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // "F"
+];
+
+const BIG_FONT_ADDRESS: usize = FONT_ADDRESS + FONT_SPRITES.len();
+const BIG_FONT_SPRITE_SIZE: usize = 10;
+
+#[rustfmt::skip]
+const BIG_FONT_SPRITES: [u8; 16 * BIG_FONT_SPRITE_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // "0"
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // "1"
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // "2"
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // "3"
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // "4"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // "5"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // "6"
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // "7"
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // "8"
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // "9"
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // "A"
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // "B"
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // "C"
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // "D"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // "E"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // "F"
+];
 
 pub struct Emulator {
     pub cpu: Cpu,
-    pub memory: [u8; MEMORY_SIZE],
-    pub active_pixels: HashSet<(u32, u32)>,
+    pub memory: Box<[u8]>,
+    pub active_pixels: Pixels,
+    /// XO-CHIP's second drawing plane, `active_pixels` being the first.
+    pub second_plane: Pixels,
     pub input: [bool; 16],
+    font: [u8; 16 * FONT_SPRITE_SIZE],
+    /// `input` as of the end of the last `step`, for edge detection.
+    previous_input: [bool; 16],
+    cpu_clock: CpuClock,
     cpu_timer: Duration,
     sound_timer: Duration,
     delay_timer: Duration,
+    profiling: bool,
+    instruction_histogram: HashMap<&'static str, u64>,
+    quirk_preset: Option<QuirkPreset>,
+    display_wait_quirk: bool,
+    address_carry_quirk: bool,
+    logic_vf_reset_quirk: bool,
+    waiting_for_vblank: bool,
+    shift_quirk: ShiftQuirk,
+    jump_quirk: JumpQuirk,
+    memory_increment_quirk: MemoryIncrementQuirk,
+    wrap_mode: WrapMode,
+    scroll_quirk: ScrollQuirk,
+    collision_quirk: CollisionQuirk,
+    log_level: LogLevel,
+    unknown_opcode_policy: UnknownOpcodePolicy,
+    halted: bool,
+    last_error: Option<Chip8Error>,
+    rng: StdRng,
+    breakpoints: HashSet<u16>,
+    resume_from_breakpoint: bool,
+    memory_watches: HashSet<u16>,
+    watch_hit: Option<MemoryWrite>,
+    rom: Vec<u8>,
+    strict_program_length: bool,
+    strict_alignment: bool,
+    byte_swap_on_load: bool,
+    selected_planes: u8,
+    sound_pattern: Option<[u8; 16]>,
+    pitch: u8,
+    keys_pressed_while_waiting: [bool; 16],
+    cycles: u64,
+    input_recorder: Option<Box<dyn Write>>,
+    recorded_input: [bool; 16],
+    input_replay: VecDeque<(u64, [bool; 16])>,
+    trace: Option<TraceFn>,
+    rewind_depth: usize,
+    rewind_buffer: VecDeque<SaveState>,
+    machine_code_handler: Option<MachineCodeFn>,
+    load_address: usize,
 }
 
+/// Called with the program counter, opcode and CPU state before an instruction executes.
+pub type TraceFn = Box<dyn FnMut(u16, u16, &Cpu)>;
+
+/// Stands in for the native routine at the given address called by `0NNN`.
+pub type MachineCodeFn = Box<dyn FnMut(u16, &mut Cpu)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Cpu {
     pub registers: [u8; 16],
     pub register_i: u16,
@@ -28,8 +154,501 @@ pub struct Cpu {
     pub sound_timer: u8,
 }
 
+impl Cpu {
+    fn new() -> Cpu {
+        Cpu {
+            registers: [0; 16],
+            register_i: 0,
+            program_counter: PROGRAM_ADDRESS as u16,
+            stack: [0; 16],
+            stack_index: -1,
+            delay_timer: 0,
+            sound_timer: 0,
+        }
+    }
+}
+
+/// Display resolution; the screen is cleared whenever it changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Resolution {
+    /// 64x32, the original CHIP-8 display.
+    #[default]
+    Low,
+    /// 128x64, the SUPER-CHIP extended display.
+    High,
+}
+
+impl Resolution {
+    pub fn width(self) -> u32 {
+        match self {
+            Resolution::Low => SCREEN_WIDTH,
+            Resolution::High => HIRES_SCREEN_WIDTH,
+        }
+    }
+
+    pub fn height(self) -> u32 {
+        match self {
+            Resolution::Low => SCREEN_HEIGHT,
+            Resolution::High => HIRES_SCREEN_HEIGHT,
+        }
+    }
+}
+
+/// The display as one bit per pixel, one `u128` per row with the leftmost pixel in
+/// the most significant bit. In low resolution only the upper 64 bits are used.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "PixelsBitmap", try_from = "PixelsBitmap")]
+pub struct Pixels {
+    resolution: Resolution,
+    rows: Vec<u128>,
+}
+
+/// How `Pixels` are serialized: the display packed 8 pixels per byte, row-major.
+#[derive(Serialize, Deserialize)]
+struct PixelsBitmap {
+    resolution: Resolution,
+    bitmap: Vec<u8>,
+}
+
+impl Pixels {
+    pub fn new() -> Pixels {
+        Pixels::with_resolution(Resolution::Low)
+    }
+
+    pub fn with_resolution(resolution: Resolution) -> Pixels {
+        Pixels {
+            resolution,
+            rows: vec![0; resolution.height() as usize],
+        }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    pub fn width(&self) -> u32 {
+        self.resolution.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.resolution.height()
+    }
+
+    pub fn rows(&self) -> &[u128] {
+        &self.rows
+    }
+
+    pub fn contains(&self, pixel: &(u32, u32)) -> bool {
+        let (x, y) = *pixel;
+        x < self.width() && y < self.height() && self.rows[y as usize] & Pixels::mask(x) != 0
+    }
+
+    /// Turns the pixel on and returns whether it was off. Pixels outside the
+    /// display are ignored.
+    pub fn insert(&mut self, pixel: (u32, u32)) -> bool {
+        let (x, y) = pixel;
+        if x >= self.width() || y >= self.height() {
+            return false;
+        }
+        let was_off = !self.contains(&pixel);
+        self.rows[y as usize] |= Pixels::mask(x);
+        was_off
+    }
+
+    /// Turns the pixel off and returns whether it was on.
+    pub fn remove(&mut self, pixel: &(u32, u32)) -> bool {
+        let was_on = self.contains(pixel);
+        if was_on {
+            self.rows[pixel.1 as usize] &= !Pixels::mask(pixel.0);
+        }
+        was_on
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.fill(0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.iter().map(|row| row.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|row| *row == 0)
+    }
+
+    /// The coordinates of the pixels that are on, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let width = self.width();
+        self.rows.iter().enumerate().flat_map(move |(y, row)| {
+            (0..width)
+                .filter(move |x| row & Pixels::mask(*x) != 0)
+                .map(move |x| (x, y as u32))
+        })
+    }
+
+    /// Bits of a row that lie on screen.
+    fn row_mask(&self) -> u128 {
+        !0 << (u128::BITS - self.width())
+    }
+
+    /// Moves every pixel `n` rows down, discarding the rows pushed off screen.
+    fn scroll_down(&mut self, n: usize) {
+        let n = n.min(self.rows.len());
+        self.rows.rotate_right(n);
+        self.rows[..n].fill(0);
+    }
+
+    fn scroll_right(&mut self, n: u32) {
+        let row_mask = self.row_mask();
+        for row in self.rows.iter_mut() {
+            *row = row.checked_shr(n).unwrap_or(0) & row_mask;
+        }
+    }
+
+    fn scroll_left(&mut self, n: u32) {
+        for row in self.rows.iter_mut() {
+            *row = row.checked_shl(n).unwrap_or(0);
+        }
+    }
+
+    /// XORs `bits` onto row `y` and returns whether any pixel was turned off.
+    fn xor_row(&mut self, y: u32, bits: u128) -> bool {
+        let row = &mut self.rows[y as usize];
+        let collision = *row & bits != 0;
+        *row ^= bits;
+        collision
+    }
+
+    fn mask(x: u32) -> u128 {
+        1 << (u128::BITS - 1 - x)
+    }
+}
+
+impl Default for Pixels {
+    fn default() -> Pixels {
+        Pixels::new()
+    }
+}
+
+impl From<Pixels> for PixelsBitmap {
+    fn from(pixels: Pixels) -> PixelsBitmap {
+        let bytes_per_row = (pixels.width() / 8) as usize;
+        let mut bitmap = Vec::with_capacity(bytes_per_row * pixels.rows.len());
+        for row in &pixels.rows {
+            bitmap.extend_from_slice(&row.to_be_bytes()[..bytes_per_row]);
+        }
+        PixelsBitmap {
+            resolution: pixels.resolution,
+            bitmap,
+        }
+    }
+}
+
+impl TryFrom<PixelsBitmap> for Pixels {
+    type Error = String;
+
+    fn try_from(bitmap: PixelsBitmap) -> Result<Pixels, String> {
+        let mut pixels = Pixels::with_resolution(bitmap.resolution);
+        let bytes_per_row = (pixels.width() / 8) as usize;
+        if bitmap.bitmap.len() != bytes_per_row * pixels.rows.len() {
+            return Err(format!(
+                "bitmap of {} bytes does not match a {}x{} display",
+                bitmap.bitmap.len(),
+                pixels.width(),
+                pixels.height()
+            ));
+        }
+        for (row, bytes) in pixels
+            .rows
+            .iter_mut()
+            .zip(bitmap.bitmap.chunks(bytes_per_row))
+        {
+            let mut row_bytes = [0; 16];
+            row_bytes[..bytes_per_row].copy_from_slice(bytes);
+            *row = u128::from_be_bytes(row_bytes);
+        }
+        Ok(pixels)
+    }
+}
+
+/// A snapshot of the machine state, for quicksaves. Quirks, clock and debugging
+/// settings are not part of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveState {
+    pub cpu: Cpu,
+    pub memory: Vec<u8>,
+    pub active_pixels: Pixels,
+    pub second_plane: Pixels,
+    pub input: [bool; 16],
+    selected_planes: u8,
+    sound_pattern: Option<[u8; 16]>,
+    pitch: u8,
+    keys_pressed_while_waiting: [bool; 16],
+    cpu_timer: Duration,
+    sound_timer: Duration,
+    delay_timer: Duration,
+}
+
+impl SaveState {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("save state serializes to memory")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaveState, Chip8Error> {
+        let state: SaveState = bincode::deserialize(bytes).map_err(Chip8Error::InvalidSaveState)?;
+        state.check_memory_size()?;
+        Ok(state)
+    }
+
+    fn check_memory_size(&self) -> Result<(), Chip8Error> {
+        if self.memory.len() == MEMORY_SIZE || self.memory.len() == XO_CHIP_MEMORY_SIZE {
+            return Ok(());
+        }
+        Err(SaveState::invalid(format!(
+            "memory of {} bytes is neither 4KB nor 64KB",
+            self.memory.len()
+        )))
+    }
+
+    fn invalid(message: String) -> Chip8Error {
+        Chip8Error::InvalidSaveState(Box::new(bincode::ErrorKind::Custom(message)))
+    }
+}
+
+/// The machine state as reported by `Emulator::to_json`. Each display row is a hex
+/// string with the leftmost pixel in the most significant bit.
+#[derive(Serialize)]
+struct JsonState<'a> {
+    #[serde(flatten)]
+    cpu: &'a Cpu,
+    resolution: Resolution,
+    display: Vec<String>,
+}
+
+impl Extend<(u32, u32)> for Pixels {
+    fn extend<T: IntoIterator<Item = (u32, u32)>>(&mut self, pixels: T) {
+        for pixel in pixels {
+            self.insert(pixel);
+        }
+    }
+}
+
+impl FromIterator<(u32, u32)> for Pixels {
+    fn from_iter<T: IntoIterator<Item = (u32, u32)>>(pixels: T) -> Pixels {
+        let mut result = Pixels::new();
+        result.extend(pixels);
+        result
+    }
+}
+
+/// Index into a palette like `DEFAULT_RGBA_PALETTE` for a pixel, one bit per plane
+/// it is on.
+pub fn palette_index(first_plane: &Pixels, second_plane: &Pixels, pixel: (u32, u32)) -> usize {
+    first_plane.contains(&pixel) as usize | (second_plane.contains(&pixel) as usize) << 1
+}
+
+#[derive(Debug)]
+pub enum Chip8Error {
+    Io(io::Error),
+    ProgramTooLarge { size: usize, capacity: usize },
+    AddressOutOfBounds(u16),
+    StackOverflow,
+    StackUnderflow,
+    InvalidSaveState(bincode::Error),
+    UnknownOpcode(u16),
+    OddProgramLength(usize),
+    MisalignedProgramCounter(u16),
+    InvalidMemorySize(usize),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::Io(e) => write!(f, "failed to read program: {}", e),
+            Chip8Error::ProgramTooLarge { size, capacity } => write!(
+                f,
+                "program of {} bytes does not fit in {} bytes of program memory",
+                size, capacity
+            ),
+            Chip8Error::AddressOutOfBounds(address) => {
+                write!(f, "address {:#06x} is out of memory bounds", address)
+            }
+            Chip8Error::StackOverflow => write!(f, "stack overflow"),
+            Chip8Error::StackUnderflow => write!(f, "return with an empty stack"),
+            Chip8Error::InvalidSaveState(e) => write!(f, "invalid save state: {}", e),
+            Chip8Error::UnknownOpcode(opcode) => write!(f, "unknown instruction {:#06x}", opcode),
+            Chip8Error::OddProgramLength(size) => {
+                write!(
+                    f,
+                    "program of {} bytes does not end on an instruction",
+                    size
+                )
+            }
+            Chip8Error::MisalignedProgramCounter(address) => {
+                write!(f, "program counter moved to odd address {:#06x}", address)
+            }
+            Chip8Error::InvalidMemorySize(size) => write!(
+                f,
+                "memory of {} bytes does not fit the fonts and a program in 64KB",
+                size
+            ),
+        }
+    }
+}
+
+// NOTE(panmar): The I/O and bincode errors are not `Clone`, so their copies keep
+// only the kind and message
+impl Clone for Chip8Error {
+    fn clone(&self) -> Chip8Error {
+        match self {
+            Chip8Error::Io(e) => Chip8Error::Io(io::Error::new(e.kind(), e.to_string())),
+            Chip8Error::ProgramTooLarge { size, capacity } => Chip8Error::ProgramTooLarge {
+                size: *size,
+                capacity: *capacity,
+            },
+            Chip8Error::AddressOutOfBounds(address) => Chip8Error::AddressOutOfBounds(*address),
+            Chip8Error::StackOverflow => Chip8Error::StackOverflow,
+            Chip8Error::StackUnderflow => Chip8Error::StackUnderflow,
+            Chip8Error::InvalidSaveState(e) => {
+                Chip8Error::InvalidSaveState(Box::new(bincode::ErrorKind::Custom(e.to_string())))
+            }
+            Chip8Error::UnknownOpcode(opcode) => Chip8Error::UnknownOpcode(*opcode),
+            Chip8Error::OddProgramLength(size) => Chip8Error::OddProgramLength(*size),
+            Chip8Error::MisalignedProgramCounter(address) => {
+                Chip8Error::MisalignedProgramCounter(*address)
+            }
+            Chip8Error::InvalidMemorySize(size) => Chip8Error::InvalidMemorySize(*size),
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Chip8Error::Io(e) => Some(e),
+            Chip8Error::InvalidSaveState(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Chip8Error {
+    fn from(e: io::Error) -> Chip8Error {
+        Chip8Error::Io(e)
+    }
+}
+
+/// Source operand of the `8XY6`/`8XYE` shift instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShiftQuirk {
+    /// `VX = VX >> 1` (CHIP-48, SUPER-CHIP).
+    InPlace,
+    /// `VX = VY >> 1` (COSMAC VIP, XO-CHIP).
+    CopyFromVy,
+}
+
+/// Base register of the `BNNN` jump.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JumpQuirk {
+    /// Jump to `NNN + V0` (COSMAC VIP, XO-CHIP).
+    V0Offset,
+    /// Jump to `XNN + VX` (CHIP-48, SUPER-CHIP).
+    VxOffset,
+}
+
+/// How `FX55`/`FX65` leave the I register after copying registers `V0..=VX`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryIncrementQuirk {
+    /// I is left untouched (SUPER-CHIP).
+    Unchanged,
+    /// I is incremented by X (CHIP-48).
+    IncrementByX,
+    /// I is incremented by X + 1 (COSMAC VIP, XO-CHIP).
+    IncrementByXPlus1,
+}
+
+/// Unit of the `00CN`/`00FB`/`00FC` scroll distances in low resolution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollQuirk {
+    /// Distances are in pixels of the active resolution (XO-CHIP).
+    ScreenPixels,
+    /// Distances are in high resolution pixels, so halved in low resolution (SUPER-CHIP 1.1).
+    HalvedInLowResolution,
+}
+
+/// What `DXYN` reports in VF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionQuirk {
+    /// 1 if any pixel was turned off, 0 otherwise.
+    Flag,
+    /// In high resolution, the number of sprite rows that turned a pixel off or were
+    /// clipped at the bottom edge (SUPER-CHIP 1.1).
+    CountRows,
+}
+
+/// Rate at which `Emulator::step` executes instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuClock {
+    Hz(u32),
+    /// One instruction per `step` call, however little time elapsed.
+    Unthrottled,
+}
+
+/// What happens to the parts of a sprite that extend past the screen edge.
+/// The sprite origin always wraps around the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Pixels past the right or bottom edge are not drawn.
+    Clip,
+    /// Pixels past the right or bottom edge wrap around to the opposite side.
+    Wrap,
+}
+
+/// Named bundles of quirk settings matching the behavior of well-known interpreters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuirkPreset {
+    CosmacVip,
+    Chip48,
+    SuperChip,
+    XoChip,
+}
+
+impl QuirkPreset {
+    pub const ALL: [QuirkPreset; 4] = [
+        QuirkPreset::CosmacVip,
+        QuirkPreset::Chip48,
+        QuirkPreset::SuperChip,
+        QuirkPreset::XoChip,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuirkPreset::CosmacVip => "chip8",
+            QuirkPreset::Chip48 => "chip48",
+            QuirkPreset::SuperChip => "superchip",
+            QuirkPreset::XoChip => "xochip",
+        }
+    }
+
+    /// Whether programs for this interpreter expect XO-CHIP's 64KB of memory.
+    pub fn uses_wide_memory(&self) -> bool {
+        *self == QuirkPreset::XoChip
+    }
+
+    /// Returns the preset following this one, wrapping around after the last one.
+    pub fn next(&self) -> QuirkPreset {
+        let index = QuirkPreset::ALL
+            .iter()
+            .position(|preset| preset == self)
+            .unwrap();
+        QuirkPreset::ALL[(index + 1) % QuirkPreset::ALL.len()]
+    }
+}
+
 #[rustfmt::skip]
-enum Instruction {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    CallMachineCode { address: u16 },
     ClearDisplay,
     Return,
     Jump { address: u16 },
@@ -45,9 +664,9 @@ enum Instruction {
     BitwiseXor { register_lhs: usize, register_rhs: usize },
     AddRegToReg { register_lhs: usize, register_rhs: usize },
     SubReg2FromReg1 { register_lhs: usize, register_rhs: usize },
-    BitwiseShrBy1 { register: usize },
+    BitwiseShrBy1 { register_lhs: usize, register_rhs: usize },
     SubReg1FromReg2 { register_lhs: usize, register_rhs: usize },
-    BitwiseShlBy1 { register: usize },
+    BitwiseShlBy1 { register_lhs: usize, register_rhs: usize },
     CondRegNotEqReg { register_lhs: usize, register_rhs: usize },
     SetAddress { address: u16 },
     JumpWithV0Offset { address: u16 },
@@ -61,15 +680,94 @@ enum Instruction {
     SetSoundTimer { register: usize },
     AddRegToAddressWithoutCarry { register: usize },
     SetAddressOfFontChar { register: usize },
+    SetAddressOfBigFontChar { register: usize },
+    SetLongAddress,
     StoreRegBcd { register: usize },
     StoreRegisters { last_register: usize },
     LoadRegisters { last_register: usize },
 
+    ScrollDown { n_rows: usize },
+    ScrollRight,
+    ScrollLeft,
+    LowResolution,
+    HighResolution,
+
+    SelectPlanes { planes: u8 },
+    StoreRegisterRange { register_first: usize, register_last: usize },
+    LoadRegisterRange { register_first: usize, register_last: usize },
+    LoadAudioPattern,
+    SetPitch { register: usize },
+
     Unknown { opcode: u16 },
 }
 
 impl Instruction {
-    fn decode(opcode: u16) -> Instruction {
+    fn name(&self) -> &'static str {
+        use Instruction::*;
+        match self {
+            ClearDisplay => "ClearDisplay",
+            Return => "Return",
+            Jump { .. } => "Jump",
+            Call { .. } => "Call",
+            SkipIfRegEqConstant { .. } => "SkipIfRegEqConstant",
+            SkipIfRegNotEqConstant { .. } => "SkipIfRegNotEqConstant",
+            SkipIfRegEqReg { .. } => "SkipIfRegEqReg",
+            SetRegToConstant { .. } => "SetRegToConstant",
+            AddConstToReg { .. } => "AddConstToReg",
+            SetRegToReg { .. } => "SetRegToReg",
+            BitwiseOr { .. } => "BitwiseOr",
+            BitwiseAnd { .. } => "BitwiseAnd",
+            BitwiseXor { .. } => "BitwiseXor",
+            AddRegToReg { .. } => "AddRegToReg",
+            SubReg2FromReg1 { .. } => "SubReg2FromReg1",
+            BitwiseShrBy1 { .. } => "BitwiseShrBy1",
+            SubReg1FromReg2 { .. } => "SubReg1FromReg2",
+            BitwiseShlBy1 { .. } => "BitwiseShlBy1",
+            CondRegNotEqReg { .. } => "CondRegNotEqReg",
+            SetAddress { .. } => "SetAddress",
+            JumpWithV0Offset { .. } => "JumpWithV0Offset",
+            BitwiseAndWithRand { .. } => "BitwiseAndWithRand",
+            DisplaySprite { .. } => "DisplaySprite",
+            SkipIfKeyPressed { .. } => "SkipIfKeyPressed",
+            SkipIfKeyNotPressed { .. } => "SkipIfKeyNotPressed",
+            SetRegToDelayTimer { .. } => "SetRegToDelayTimer",
+            AwaitAndSetKeyPress { .. } => "AwaitAndSetKeyPress",
+            SetDelayTimer { .. } => "SetDelayTimer",
+            SetSoundTimer { .. } => "SetSoundTimer",
+            AddRegToAddressWithoutCarry { .. } => "AddRegToAddressWithoutCarry",
+            SetAddressOfFontChar { .. } => "SetAddressOfFontChar",
+            SetAddressOfBigFontChar { .. } => "SetAddressOfBigFontChar",
+            SetLongAddress => "SetLongAddress",
+            StoreRegBcd { .. } => "StoreRegBcd",
+            StoreRegisters { .. } => "StoreRegisters",
+            LoadRegisters { .. } => "LoadRegisters",
+            ScrollDown { .. } => "ScrollDown",
+            ScrollRight => "ScrollRight",
+            ScrollLeft => "ScrollLeft",
+            LowResolution => "LowResolution",
+            HighResolution => "HighResolution",
+            SelectPlanes { .. } => "SelectPlanes",
+            StoreRegisterRange { .. } => "StoreRegisterRange",
+            LoadRegisterRange { .. } => "LoadRegisterRange",
+            LoadAudioPattern => "LoadAudioPattern",
+            SetPitch { .. } => "SetPitch",
+            CallMachineCode { .. } => "CallMachineCode",
+
+            Unknown { .. } => "Unknown",
+        }
+    }
+
+    /// Decodes a big-endian opcode; opcodes of no instruction become `Unknown`.
+    ///
+    /// ```
+    /// use chip8_emulator::Instruction;
+    ///
+    /// assert_eq!(
+    ///     Instruction::decode(0xD123),
+    ///     Instruction::DisplaySprite { register_x: 1, register_y: 2, n_bytes: 3 }
+    /// );
+    /// ```
+    pub fn decode(opcode: u16) -> Instruction {
         let hex_digits: [u8; 4] = [
             ((opcode & 0xf000) >> 12) as u8,
             ((opcode & 0x0f00) >> 8) as u8,
@@ -81,6 +779,18 @@ impl Instruction {
         match hex_digits {
             [0x0, 0, 0xE, 0] => ClearDisplay,
             [0x0, 0, 0xE, 0xE] => Return,
+            [0x0, 0, 0xC, n_rows] => ScrollDown {
+                n_rows: n_rows as usize,
+            },
+            [0x0, 0, 0xF, 0xB] => ScrollRight,
+            [0x0, 0, 0xF, 0xC] => ScrollLeft,
+            [0x0, 0, 0xF, 0xE] => LowResolution,
+            [0x0, 0, 0xF, 0xF] => HighResolution,
+            // NOTE(panmar): 0000 is left unknown, it is far more likely zeroed memory
+            // than a call to a routine at address 0
+            [0x0, _, _, _] if opcode != 0x0000 => CallMachineCode {
+                address: opcode & 0x0fff,
+            },
             [0x1, _, _, _] => Jump {
                 address: opcode & 0x0fff,
             },
@@ -99,6 +809,14 @@ impl Instruction {
                 register_lhs: register_lhs as usize,
                 register_rhs: register_rhs as usize,
             },
+            [0x5, register_first, register_last, 2] => StoreRegisterRange {
+                register_first: register_first as usize,
+                register_last: register_last as usize,
+            },
+            [0x5, register_first, register_last, 3] => LoadRegisterRange {
+                register_first: register_first as usize,
+                register_last: register_last as usize,
+            },
             [0x6, register, _, _] => SetRegToConstant {
                 register: register as usize,
                 constant: (opcode & 0x00ff) as u8,
@@ -131,15 +849,17 @@ impl Instruction {
                 register_lhs: register_lhs as usize,
                 register_rhs: register_rhs as usize,
             },
-            [0x8, register, _, 6] => BitwiseShrBy1 {
-                register: register as usize,
+            [0x8, register_lhs, register_rhs, 6] => BitwiseShrBy1 {
+                register_lhs: register_lhs as usize,
+                register_rhs: register_rhs as usize,
             },
             [0x8, register_lhs, register_rhs, 7] => SubReg1FromReg2 {
                 register_lhs: register_lhs as usize,
                 register_rhs: register_rhs as usize,
             },
-            [0x8, register, _, 0xE] => BitwiseShlBy1 {
-                register: register as usize,
+            [0x8, register_lhs, register_rhs, 0xE] => BitwiseShlBy1 {
+                register_lhs: register_lhs as usize,
+                register_rhs: register_rhs as usize,
             },
             [0x9, register_lhs, register_rhs, 0x0] => CondRegNotEqReg {
                 register_lhs: register_lhs as usize,
@@ -184,6 +904,15 @@ impl Instruction {
             [0xF, register, 0x2, 0x9] => SetAddressOfFontChar {
                 register: register as usize,
             },
+            [0xF, register, 0x3, 0x0] => SetAddressOfBigFontChar {
+                register: register as usize,
+            },
+            [0xF, 0x0, 0x0, 0x0] => SetLongAddress,
+            [0xF, planes, 0x0, 0x1] => SelectPlanes { planes },
+            [0xF, 0x0, 0x0, 0x2] => LoadAudioPattern,
+            [0xF, register, 0x3, 0xA] => SetPitch {
+                register: register as usize,
+            },
             [0xF, register, 0x3, 0x3] => StoreRegBcd {
                 register: register as usize,
             },
@@ -197,10 +926,9 @@ impl Instruction {
         }
     }
 
-    #[allow(dead_code)]
-    fn to_opcode(&self) -> u16 {
+    pub fn to_opcode(self) -> u16 {
         use Instruction::*;
-        let opcode = match self {
+        let opcode = match &self {
             ClearDisplay => 0x00E0,
             Return => 0x00EE,
             Jump { address } => 0x1000 | address,
@@ -245,12 +973,18 @@ impl Instruction {
                 register_lhs,
                 register_rhs,
             } => 0x8005 | ((*register_lhs as u16) << 8) | ((*register_rhs as u16) << 4),
-            BitwiseShrBy1 { register } => 0x8006 | ((*register as u16) << 8),
+            BitwiseShrBy1 {
+                register_lhs,
+                register_rhs,
+            } => 0x8006 | ((*register_lhs as u16) << 8) | ((*register_rhs as u16) << 4),
             SubReg1FromReg2 {
                 register_lhs,
                 register_rhs,
             } => 0x8007 | ((*register_lhs as u16) << 8) | ((*register_rhs as u16) << 4),
-            BitwiseShlBy1 { register } => 0x800E | ((*register as u16) << 8),
+            BitwiseShlBy1 {
+                register_lhs,
+                register_rhs,
+            } => 0x800E | ((*register_lhs as u16) << 8) | ((*register_rhs as u16) << 4),
             CondRegNotEqReg {
                 register_lhs,
                 register_rhs,
@@ -275,9 +1009,28 @@ impl Instruction {
             SetSoundTimer { register } => 0xF018 | ((*register as u16) << 8),
             AddRegToAddressWithoutCarry { register } => 0xF01E | ((*register as u16) << 8),
             SetAddressOfFontChar { register } => 0xF029 | ((*register as u16) << 8),
+            SetAddressOfBigFontChar { register } => 0xF030 | ((*register as u16) << 8),
+            SetLongAddress => 0xF000,
             StoreRegBcd { register } => 0xF033 | ((*register as u16) << 8),
             StoreRegisters { last_register } => 0xF055 | ((*last_register as u16) << 8),
             LoadRegisters { last_register } => 0xF065 | ((*last_register as u16) << 8),
+            ScrollDown { n_rows } => 0x00C0 | *n_rows as u16,
+            ScrollRight => 0x00FB,
+            ScrollLeft => 0x00FC,
+            LowResolution => 0x00FE,
+            HighResolution => 0x00FF,
+            SelectPlanes { planes } => 0xF001 | ((*planes as u16) << 8),
+            StoreRegisterRange {
+                register_first,
+                register_last,
+            } => 0x5002 | ((*register_first as u16) << 8) | ((*register_last as u16) << 4),
+            LoadRegisterRange {
+                register_first,
+                register_last,
+            } => 0x5003 | ((*register_first as u16) << 8) | ((*register_last as u16) << 4),
+            LoadAudioPattern => 0xF002,
+            SetPitch { register } => 0xF03A | ((*register as u16) << 8),
+            CallMachineCode { address } => *address,
 
             Unknown { opcode } => *opcode,
         };
@@ -285,500 +1038,1867 @@ impl Instruction {
     }
 }
 
-enum InstructionExecuteStatus {
-    Complete,
-    InProgress,
-}
-
-impl Emulator {
-    pub fn new() -> Emulator {
-        let mut emulator = Emulator {
-            cpu: Cpu {
-                registers: [0; 16],
-                register_i: 0,
-                program_counter: 512,
-                stack: [0; 16],
-                stack_index: -1,
-                delay_timer: 0,
-                sound_timer: 0,
-            },
-            memory: [0; MEMORY_SIZE],
-            active_pixels: HashSet::new(),
-            input: [false; 16],
-            cpu_timer: Duration::MAX,
-            sound_timer: Duration::ZERO,
-            delay_timer: Duration::ZERO,
-        };
-
-        fn load_font_sprites(memory: &mut [u8; MEMORY_SIZE]) {
-            // "0"
-            memory[0x0000 + 0] = 0xF0;
-            memory[0x0000 + 2] = 0x90;
-            memory[0x0000 + 4] = 0x90;
-            memory[0x0000 + 6] = 0x90;
-            memory[0x0000 + 8] = 0xF0;
-
-            // "1"
-            memory[0x000A + 0] = 0x20;
-            memory[0x000A + 2] = 0x60;
-            memory[0x000A + 4] = 0x20;
-            memory[0x000A + 6] = 0x20;
-            memory[0x000A + 8] = 0x70;
-
-            // "2"
-            memory[0x0014 + 0] = 0xF0;
-            memory[0x0014 + 2] = 0x10;
-            memory[0x0014 + 4] = 0xF0;
-            memory[0x0014 + 6] = 0x80;
-            memory[0x0014 + 8] = 0xF0;
-
-            // "3"
-            memory[0x001E + 0] = 0xF0;
-            memory[0x001E + 2] = 0x10;
-            memory[0x001E + 4] = 0xF0;
-            memory[0x001E + 6] = 0x10;
-            memory[0x001E + 8] = 0xF0;
-
-            // "4"
-            memory[0x0028 + 0] = 0x90;
-            memory[0x0028 + 2] = 0x90;
-            memory[0x0028 + 4] = 0xF0;
-            memory[0x0028 + 6] = 0x10;
-            memory[0x0028 + 8] = 0x10;
-
-            // "5"
-            memory[0x0032 + 0] = 0xF0;
-            memory[0x0032 + 2] = 0x80;
-            memory[0x0032 + 4] = 0xF0;
-            memory[0x0032 + 6] = 0x10;
-            memory[0x0032 + 8] = 0xF0;
-
-            // "6"
-            memory[0x003C + 0] = 0xF0;
-            memory[0x003C + 2] = 0x80;
-            memory[0x003C + 4] = 0xF0;
-            memory[0x003C + 6] = 0x90;
-            memory[0x003C + 8] = 0xF0;
-
-            // "7"
-            memory[0x0046 + 0] = 0xF0;
-            memory[0x0046 + 2] = 0x10;
-            memory[0x0046 + 4] = 0x20;
-            memory[0x0046 + 6] = 0x40;
-            memory[0x0046 + 8] = 0x40;
-
-            // "8"
-            memory[0x0050 + 0] = 0xF0;
-            memory[0x0050 + 2] = 0x90;
-            memory[0x0050 + 4] = 0xF0;
-            memory[0x0050 + 6] = 0x90;
-            memory[0x0050 + 8] = 0xF0;
-
-            // "9"
-            memory[0x005A + 0] = 0xF0;
-            memory[0x005A + 2] = 0x90;
-            memory[0x005A + 4] = 0xF0;
-            memory[0x005A + 6] = 0x10;
-            memory[0x005A + 8] = 0xF0;
-
-            // "A"
-            memory[0x0064 + 0] = 0xF0;
-            memory[0x0064 + 2] = 0x90;
-            memory[0x0064 + 4] = 0xF0;
-            memory[0x0064 + 6] = 0x90;
-            memory[0x0064 + 8] = 0x90;
-
-            // "B"
-            memory[0x006E + 0] = 0xE0;
-            memory[0x006E + 2] = 0x90;
-            memory[0x006E + 4] = 0xE0;
-            memory[0x006E + 6] = 0x90;
-            memory[0x006E + 8] = 0xE0;
-
-            // "C"
-            memory[0x0078 + 0] = 0xF0;
-            memory[0x0078 + 2] = 0x80;
-            memory[0x0078 + 4] = 0x80;
-            memory[0x0078 + 6] = 0x80;
-            memory[0x0078 + 8] = 0xF0;
-
-            // "D"
-            memory[0x0082 + 0] = 0xE0;
-            memory[0x0082 + 2] = 0x90;
-            memory[0x0082 + 4] = 0x90;
-            memory[0x0082 + 6] = 0x90;
-            memory[0x0082 + 8] = 0xE0;
-
-            // "E"
-            memory[0x008C + 0] = 0xF0;
-            memory[0x008C + 2] = 0x80;
-            memory[0x008C + 4] = 0xF0;
-            memory[0x008C + 6] = 0x80;
-            memory[0x008C + 8] = 0xF0;
-
-            // "F"
-            memory[0x0096 + 0] = 0xF0;
-            memory[0x0096 + 2] = 0x80;
-            memory[0x0096 + 4] = 0xF0;
-            memory[0x0096 + 6] = 0x80;
-            memory[0x0096 + 8] = 0x80;
-        }
-
-        load_font_sprites(&mut emulator.memory);
-        emulator
-    }
-
-    #[allow(dead_code)]
-    fn load_instructions(&mut self, instructions: Vec<Instruction>) {
-        let mut data: Vec<u8> = Vec::new();
-        for instruction in instructions {
-            let opcode = instruction.to_opcode();
-            data.push(((opcode & 0xFF00) >> 8) as u8);
-            data.push((opcode & 0x00FF) as u8);
-        }
-        self.load_program_from_data(&data);
-    }
-
-    pub fn load_program_from_file(&mut self, filepath: &str) {
-        self.load_program_from_data(&fs::read(filepath).unwrap());
-    }
-
-    pub fn load_program_from_data(&mut self, data: &Vec<u8>) {
-        let mut i = 512;
-        for p in data {
-            self.memory[i] = *p;
-            i += 1;
-        }
-
-        self.cpu.program_counter = 512;
+impl From<u16> for Instruction {
+    fn from(opcode: u16) -> Instruction {
+        Instruction::decode(opcode)
     }
+}
 
-    pub fn step(&mut self, elapsed_time: Duration) {
-        self.cpu_timer = self.cpu_timer.saturating_add(elapsed_time);
-        self.delay_timer = self.delay_timer.saturating_add(elapsed_time);
-        self.sound_timer = self.sound_timer.saturating_add(elapsed_time);
-
-        if self.delay_timer >= Duration::from_millis(16) {
-            self.cpu.delay_timer = self.cpu.delay_timer.saturating_sub(1);
-            self.delay_timer = Duration::ZERO;
-        }
-
-        if self.sound_timer >= Duration::from_millis(16) {
-            self.cpu.sound_timer = self.cpu.sound_timer.saturating_sub(1);
-            self.sound_timer = Duration::ZERO;
-        }
-
-        if self.cpu_timer >= Duration::from_millis(2) {
-            let opcode = self.fetch_opcode().unwrap();
-            let instruction = Instruction::decode(opcode);
-            match self.execute(instruction) {
-                InstructionExecuteStatus::Complete => self.cpu_timer = Duration::ZERO,
-                InstructionExecuteStatus::InProgress => {}
-            }
-        }
+impl From<Instruction> for u16 {
+    fn from(instruction: Instruction) -> u16 {
+        instruction.to_opcode()
     }
+}
 
-    fn fetch_opcode(&mut self) -> Option<u16> {
-        let opcode = u16::from_be_bytes([
-            self.memory[self.cpu.program_counter as usize],
-            self.memory[(self.cpu.program_counter + 1) as usize],
-        ]);
-
-        return Some(opcode);
+impl From<&Instruction> for u16 {
+    fn from(instruction: &Instruction) -> u16 {
+        instruction.to_opcode()
     }
+}
 
-    fn execute(&mut self, instruction: Instruction) -> InstructionExecuteStatus {
-        self.cpu.program_counter += 2;
-
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use Instruction::*;
-        match instruction {
-            ClearDisplay => {
-                self.active_pixels.clear();
-            }
-            Return => {
-                self.cpu.program_counter = self.cpu.stack[self.cpu.stack_index as usize];
-                self.cpu.stack_index -= 1;
-            }
-            Jump { address } => self.cpu.program_counter = address,
-            Call { address } => {
-                self.cpu.stack_index += 1;
-                self.cpu.stack[self.cpu.stack_index as usize] = self.cpu.program_counter;
-                self.cpu.program_counter = address;
-            }
+        match self {
+            ClearDisplay => write!(f, "CLS"),
+            Return => write!(f, "RET"),
+            Jump { address } => write!(f, "JP {:#05X}", address),
+            Call { address } => write!(f, "CALL {:#05X}", address),
             SkipIfRegEqConstant { register, constant } => {
-                if self.cpu.registers[register] == constant {
-                    self.cpu.program_counter += 2;
-                }
+                write!(f, "SE V{:X}, {:#04X}", register, constant)
             }
             SkipIfRegNotEqConstant { register, constant } => {
-                if self.cpu.registers[register] != constant {
-                    self.cpu.program_counter += 2;
-                }
+                write!(f, "SNE V{:X}, {:#04X}", register, constant)
             }
             SkipIfRegEqReg {
                 register_lhs,
                 register_rhs,
-            } => {
-                if self.cpu.registers[register_lhs] == self.cpu.registers[register_rhs] {
-                    self.cpu.program_counter += 2;
-                }
+            } => write!(f, "SE V{:X}, V{:X}", register_lhs, register_rhs),
+            SetRegToConstant { register, constant } => {
+                write!(f, "LD V{:X}, {:#04X}", register, constant)
             }
-            SetRegToConstant { register, constant } => self.cpu.registers[register] = constant,
             AddConstToReg { register, constant } => {
-                self.cpu.registers[register] = self.cpu.registers[register].wrapping_add(constant);
+                write!(f, "ADD V{:X}, {:#04X}", register, constant)
             }
             SetRegToReg {
                 register_lhs,
                 register_rhs,
-            } => self.cpu.registers[register_lhs] = self.cpu.registers[register_rhs],
+            } => write!(f, "LD V{:X}, V{:X}", register_lhs, register_rhs),
             BitwiseOr {
                 register_lhs,
                 register_rhs,
-            } => self.cpu.registers[register_lhs] |= self.cpu.registers[register_rhs],
+            } => write!(f, "OR V{:X}, V{:X}", register_lhs, register_rhs),
             BitwiseAnd {
                 register_lhs,
                 register_rhs,
-            } => self.cpu.registers[register_lhs] &= self.cpu.registers[register_rhs],
+            } => write!(f, "AND V{:X}, V{:X}", register_lhs, register_rhs),
             BitwiseXor {
                 register_lhs,
                 register_rhs,
-            } => self.cpu.registers[register_lhs] ^= self.cpu.registers[register_rhs],
+            } => write!(f, "XOR V{:X}, V{:X}", register_lhs, register_rhs),
             AddRegToReg {
                 register_lhs,
                 register_rhs,
-            } => {
-                let result = self.cpu.registers[register_lhs]
-                    .overflowing_add(self.cpu.registers[register_rhs]);
-                match result {
-                    (sum, overflow) => {
-                        self.cpu.registers[register_lhs as usize] = sum;
-                        self.cpu.registers[0xF] = overflow as u8;
-                    }
-                }
-            }
+            } => write!(f, "ADD V{:X}, V{:X}", register_lhs, register_rhs),
             SubReg2FromReg1 {
                 register_lhs,
                 register_rhs,
-            } => {
-                let result = self.cpu.registers[register_lhs]
-                    .overflowing_sub(self.cpu.registers[register_rhs]);
-                match result {
-                    (sub, overflow) => {
-                        self.cpu.registers[register_lhs] = sub;
-                        self.cpu.registers[0xF] = !overflow as u8;
-                    }
-                }
-            }
-            BitwiseShrBy1 { register } => {
-                self.cpu.registers[0xF] = self.cpu.registers[register] % 2;
-                self.cpu.registers[register] /= 2;
-            }
+            } => write!(f, "SUB V{:X}, V{:X}", register_lhs, register_rhs),
+            BitwiseShrBy1 {
+                register_lhs,
+                register_rhs,
+            } => write!(f, "SHR V{:X}, V{:X}", register_lhs, register_rhs),
             SubReg1FromReg2 {
                 register_lhs,
                 register_rhs,
-            } => {
-                let result = self.cpu.registers[register_rhs]
-                    .overflowing_sub(self.cpu.registers[register_lhs]);
-                match result {
-                    (sub, overflow) => {
-                        self.cpu.registers[register_lhs] = sub;
-                        self.cpu.registers[0xF] = !overflow as u8;
-                    }
-                }
-            }
-            BitwiseShlBy1 { register } => {
-                let result = self.cpu.registers[register].overflowing_mul(2);
-                match result {
-                    (mul, overflow) => {
-                        self.cpu.registers[register] = mul;
-                        self.cpu.registers[0xF] = overflow as u8;
-                    }
-                }
-            }
+            } => write!(f, "SUBN V{:X}, V{:X}", register_lhs, register_rhs),
+            BitwiseShlBy1 {
+                register_lhs,
+                register_rhs,
+            } => write!(f, "SHL V{:X}, V{:X}", register_lhs, register_rhs),
             CondRegNotEqReg {
                 register_lhs,
                 register_rhs,
-            } => {
-                if self.cpu.registers[register_lhs] != self.cpu.registers[register_rhs] {
-                    self.cpu.program_counter += 2;
-                }
-            }
-            SetAddress { address } => self.cpu.register_i = address,
-            JumpWithV0Offset { address } => {
-                self.cpu.program_counter = self.cpu.registers[0] as u16 + address
-            }
+            } => write!(f, "SNE V{:X}, V{:X}", register_lhs, register_rhs),
+            SetAddress { address } => write!(f, "LD I, {:#05X}", address),
+            JumpWithV0Offset { address } => write!(f, "JP V0, {:#05X}", address),
             BitwiseAndWithRand { register, constant } => {
-                let mut rng = rand::thread_rng();
-                let random_number: u8 = rng.gen();
-                self.cpu.registers[register] = constant & random_number;
+                write!(f, "RND V{:X}, {:#04X}", register, constant)
             }
             DisplaySprite {
                 register_x,
                 register_y,
                 n_bytes,
-            } => {
-                let origin_x = self.cpu.registers[register_x] as u32 % SCREEN_WIDTH;
-                let origin_y = self.cpu.registers[register_y] as u32 % SCREEN_HEIGHT;
-                let mut pixels = Vec::new();
-                for i in 0..n_bytes {
-                    let sprite = self.memory[self.cpu.register_i as usize + i as usize];
-                    let mut mask = 0b10000000;
-                    for j in 0..8 {
-                        let (pixel_x, pixel_y) = (origin_x + j, origin_y + i as u32);
-                        if (pixel_x >= SCREEN_WIDTH) || (pixel_y >= SCREEN_HEIGHT) {
-                            break;
-                        }
-                        if sprite & mask != 0 {
-                            pixels.push((pixel_x, pixel_y));
-                        }
-                        mask >>= 1;
-                    }
-                }
+            } => write!(
+                f,
+                "DRW V{:X}, V{:X}, {:#X}",
+                register_x, register_y, n_bytes
+            ),
+            SkipIfKeyPressed { register } => write!(f, "SKP V{:X}", register),
+            SkipIfKeyNotPressed { register } => write!(f, "SKNP V{:X}", register),
+            SetRegToDelayTimer { register } => write!(f, "LD V{:X}, DT", register),
+            AwaitAndSetKeyPress { register } => write!(f, "LD V{:X}, K", register),
+            SetDelayTimer { register } => write!(f, "LD DT, V{:X}", register),
+            SetSoundTimer { register } => write!(f, "LD ST, V{:X}", register),
+            AddRegToAddressWithoutCarry { register } => write!(f, "ADD I, V{:X}", register),
+            SetAddressOfFontChar { register } => write!(f, "LD F, V{:X}", register),
+            SetAddressOfBigFontChar { register } => write!(f, "LD HF, V{:X}", register),
+            SetLongAddress => write!(f, "LD I, LONG"),
+            StoreRegBcd { register } => write!(f, "LD B, V{:X}", register),
+            StoreRegisters { last_register } => write!(f, "LD [I], V{:X}", last_register),
+            LoadRegisters { last_register } => write!(f, "LD V{:X}, [I]", last_register),
+            ScrollDown { n_rows } => write!(f, "SCD {:#X}", n_rows),
+            ScrollRight => write!(f, "SCR"),
+            ScrollLeft => write!(f, "SCL"),
+            LowResolution => write!(f, "LOW"),
+            HighResolution => write!(f, "HIGH"),
+            SelectPlanes { planes } => write!(f, "PLANE {}", planes),
+            StoreRegisterRange {
+                register_first,
+                register_last,
+            } => write!(f, "SAVE V{:X}, V{:X}", register_first, register_last),
+            LoadRegisterRange {
+                register_first,
+                register_last,
+            } => write!(f, "LOAD V{:X}, V{:X}", register_first, register_last),
+            LoadAudioPattern => write!(f, "AUDIO"),
+            SetPitch { register } => write!(f, "PITCH V{:X}", register),
+            CallMachineCode { address } => write!(f, "SYS {:#05X}", address),
+
+            Unknown { opcode } => write!(f, "DW {:#06X}", opcode),
+        }
+    }
+}
 
-                let mut xored = false;
-                if !pixels.is_empty() {
-                    xored = self.draw_pixels(&pixels);
-                }
+/// Verbosity of the diagnostics the emulator prints on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Debug,
+}
 
-                if xored {
-                    self.cpu.registers[0xF] = 1;
-                } else {
-                    self.cpu.registers[0xF] = 0;
-                }
-            }
-            SkipIfKeyPressed { register } => {
-                let key = self.cpu.registers[register];
-                if self.input[key as usize] {
-                    self.cpu.program_counter += 2;
-                }
-            }
-            SkipIfKeyNotPressed { register } => {
-                let key = self.cpu.registers[register];
-                if !self.input[key as usize] {
-                    self.cpu.program_counter += 2;
-                }
-            }
-            SetRegToDelayTimer { register } => self.cpu.registers[register] = self.cpu.delay_timer,
-            AwaitAndSetKeyPress { register } => {
-                let mut key_pressed = false;
-                for (i, input) in self.input.iter().enumerate() {
-                    if *input {
-                        self.cpu.registers[register] = i as u8;
-                        key_pressed = true;
-                        break;
-                    }
-                }
-                if !key_pressed {
-                    self.cpu.program_counter -= 2;
-                    return InstructionExecuteStatus::InProgress;
-                }
-            }
-            SetDelayTimer { register } => self.cpu.delay_timer = self.cpu.registers[register],
-            SetSoundTimer { register } => self.cpu.sound_timer = self.cpu.registers[register],
-            AddRegToAddressWithoutCarry { register } => {
-                self.cpu.register_i += self.cpu.registers[register] as u16
-            }
-            SetAddressOfFontChar { register } => {
-                let character = self.cpu.registers[register];
-                self.cpu.register_i = match character {
-                    0 => 0x0000,
-                    1 => 0x000A,
-                    2 => 0x0014,
-                    3 => 0x001E,
-                    4 => 0x0028,
-                    5 => 0x0032,
-                    6 => 0x003C,
-                    7 => 0x0046,
-                    8 => 0x0050,
-                    9 => 0x005A,
-                    0xA => 0x0064,
-                    0xB => 0x006E,
-                    0xC => 0x0078,
-                    0xD => 0x0082,
-                    0xE => 0x008C,
-                    0xF => 0x0096,
-                    _ => self.cpu.register_i,
-                }
+/// What happens when the emulator executes an opcode of no known instruction.
+/// Execution continues after the opcode unless the program is halted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownOpcodePolicy {
+    Ignore,
+    Log,
+    /// Stops execution and records the opcode in `Emulator::last_error`.
+    Halt,
+    Panic,
+}
+
+/// Outcome of `Emulator::step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepStatus {
+    Running,
+    /// Stopped before executing the instruction at this address. The next `step`
+    /// executes it.
+    BreakpointHit(u16),
+    /// Stopped right after an instruction changed a watched memory cell.
+    WatchHit(MemoryWrite),
+}
+
+/// A write to memory that changed the value at `address`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryWrite {
+    pub address: u16,
+    pub old_value: u8,
+    pub new_value: u8,
+}
+
+enum InstructionExecuteStatus {
+    Complete,
+    InProgress,
+}
+
+impl Emulator {
+    pub fn new() -> Emulator {
+        let mut emulator = Emulator {
+            cpu: Cpu::new(),
+            memory: vec![0; MEMORY_SIZE].into_boxed_slice(),
+            active_pixels: Pixels::new(),
+            second_plane: Pixels::new(),
+            input: [false; 16],
+            font: FONT_SPRITES,
+            previous_input: [false; 16],
+            cpu_clock: CpuClock::Hz(500),
+            cpu_timer: Duration::ZERO,
+            sound_timer: Duration::ZERO,
+            delay_timer: Duration::ZERO,
+            profiling: false,
+            instruction_histogram: HashMap::new(),
+            quirk_preset: None,
+            display_wait_quirk: false,
+            address_carry_quirk: false,
+            logic_vf_reset_quirk: false,
+            waiting_for_vblank: false,
+            shift_quirk: ShiftQuirk::InPlace,
+            jump_quirk: JumpQuirk::V0Offset,
+            memory_increment_quirk: MemoryIncrementQuirk::Unchanged,
+            wrap_mode: WrapMode::Clip,
+            scroll_quirk: ScrollQuirk::ScreenPixels,
+            collision_quirk: CollisionQuirk::Flag,
+            log_level: LogLevel::Warn,
+            unknown_opcode_policy: UnknownOpcodePolicy::Log,
+            halted: false,
+            last_error: None,
+            rng: StdRng::from_entropy(),
+            breakpoints: HashSet::new(),
+            resume_from_breakpoint: false,
+            memory_watches: HashSet::new(),
+            watch_hit: None,
+            rom: Vec::new(),
+            strict_program_length: false,
+            strict_alignment: false,
+            byte_swap_on_load: false,
+            selected_planes: 0b01,
+            sound_pattern: None,
+            pitch: DEFAULT_PITCH,
+            keys_pressed_while_waiting: [false; 16],
+            cycles: 0,
+            input_recorder: None,
+            recorded_input: [false; 16],
+            input_replay: VecDeque::new(),
+            trace: None,
+            rewind_depth: 0,
+            rewind_buffer: VecDeque::new(),
+            machine_code_handler: None,
+            load_address: PROGRAM_ADDRESS,
+        };
+
+        emulator.load_fonts();
+        emulator
+    }
+
+    fn load_fonts(&mut self) {
+        self.memory[FONT_ADDRESS..FONT_ADDRESS + self.font.len()].copy_from_slice(&self.font);
+        self.memory[BIG_FONT_ADDRESS..BIG_FONT_ADDRESS + BIG_FONT_SPRITES.len()]
+            .copy_from_slice(&BIG_FONT_SPRITES);
+    }
+
+    /// Replaces the glyphs of the 16 hex digits used by `FX29`, 5 bytes each from "0"
+    /// to "F". The font survives `reset`.
+    pub fn set_font(&mut self, font: &[u8; 16 * FONT_SPRITE_SIZE]) {
+        self.font = *font;
+        self.load_fonts();
+    }
+
+    /// Starts configuring an emulator, see `EmulatorBuilder`.
+    pub fn builder() -> EmulatorBuilder {
+        EmulatorBuilder::new()
+    }
+
+    /// Creates an emulator whose `CXNN` results are reproducible for a given `seed`.
+    pub fn with_seed(seed: u64) -> Emulator {
+        let mut emulator = Emulator::new();
+        emulator.rng = StdRng::seed_from_u64(seed);
+        emulator
+    }
+
+    #[allow(dead_code)]
+    fn load_instructions(&mut self, instructions: Vec<Instruction>) {
+        let mut data: Vec<u8> = Vec::new();
+        for instruction in instructions {
+            let opcode = instruction.to_opcode();
+            data.push(((opcode & 0xFF00) >> 8) as u8);
+            data.push((opcode & 0x00FF) as u8);
+        }
+        self.load_program_from_data(&data).unwrap();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_program_from_file<P: AsRef<Path>>(
+        &mut self,
+        filepath: P,
+    ) -> Result<(), Chip8Error> {
+        self.load_program_from_data(&fs::read(filepath)?)
+    }
+
+    pub fn load_program_from_reader<R: Read>(&mut self, mut reader: R) -> Result<(), Chip8Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        self.load_program_from_data(&data)
+    }
+
+    pub fn load_program_from_data(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        self.check_program(data)?;
+
+        let mut rom = data.to_vec();
+        if self.byte_swap_on_load {
+            for word in rom.chunks_exact_mut(2) {
+                word.swap(0, 1);
             }
-            StoreRegBcd { register } => {
-                let mut value = self.cpu.registers[register];
-                self.memory[(self.cpu.register_i + 2) as usize] = value % 10;
-                value /= 10;
-                self.memory[(self.cpu.register_i + 1) as usize] = value % 10;
-                value /= 10;
-                self.memory[(self.cpu.register_i + 0) as usize] = value % 10;
+        }
+        self.memory[self.load_address..self.load_address + rom.len()].copy_from_slice(&rom);
+        self.cpu.program_counter = self.load_address as u16;
+        self.rom = rom;
+        Ok(())
+    }
+
+    fn check_program(&self, data: &[u8]) -> Result<(), Chip8Error> {
+        let capacity = self.memory.len().saturating_sub(self.load_address);
+        if data.len() > capacity {
+            return Err(Chip8Error::ProgramTooLarge {
+                size: data.len(),
+                capacity,
+            });
+        }
+        // NOTE(panmar): Instructions are two bytes, so the last one of an odd-length
+        // program is completed with whatever follows it in memory
+        if !data.len().is_multiple_of(2) {
+            if self.strict_program_length {
+                return Err(Chip8Error::OddProgramLength(data.len()));
             }
-            StoreRegisters { last_register } => {
-                for i in 0..=last_register {
-                    self.memory[self.cpu.register_i as usize + i] = self.cpu.registers[i];
-                }
+            self.log(
+                LogLevel::Warn,
+                &format!("program of {} bytes has an odd length", data.len()),
+            );
+        }
+        Ok(())
+    }
+
+    /// When enabled, loading a program of an odd number of bytes fails instead of
+    /// only warning about it.
+    pub fn set_strict_program_length(&mut self, enabled: bool) {
+        self.strict_program_length = enabled;
+    }
+
+    /// When enabled, programs are loaded with the bytes of each 16-bit word swapped,
+    /// for ROMs dumped little-endian by mistake. A trailing odd byte stays as is.
+    pub fn set_byte_swap_on_load(&mut self, enabled: bool) {
+        self.byte_swap_on_load = enabled;
+    }
+
+    /// When enabled, an instruction moving the program counter to an odd address
+    /// halts the emulator with an error, as instructions are only fetched from even
+    /// addresses by well-behaved programs.
+    pub fn set_strict_alignment(&mut self, enabled: bool) {
+        self.strict_alignment = enabled;
+    }
+
+    /// Size in bytes of the last loaded program.
+    pub fn program_len(&self) -> usize {
+        self.rom.len()
+    }
+
+    /// Where programs are loaded and start executing, 0x200 by default. Some
+    /// variants, like the ETI-660, use 0x600. Affects programs loaded afterwards.
+    /// Fails, keeping the current one, unless `address` is within memory.
+    pub fn set_load_address(&mut self, address: u16) -> Result<(), Chip8Error> {
+        if address as usize >= self.memory.len() {
+            return Err(Chip8Error::AddressOutOfBounds(address));
+        }
+        self.load_address = address as usize;
+        Ok(())
+    }
+
+    pub fn load_address(&self) -> u16 {
+        self.load_address as u16
+    }
+
+    /// Restarts the loaded program from a clean machine state. Memory from the
+    /// load address on is kept as is, including any changes the program made
+    /// to it. Quirks, clock and breakpoints are kept too.
+    pub fn reset(&mut self) {
+        self.cpu = Cpu::new();
+        self.cpu.program_counter = self.load_address as u16;
+        self.memory[..self.load_address].fill(0);
+        self.load_fonts();
+        self.active_pixels = Pixels::with_resolution(Resolution::Low);
+        self.second_plane = Pixels::with_resolution(Resolution::Low);
+        self.selected_planes = 0b01;
+        self.sound_pattern = None;
+        self.pitch = DEFAULT_PITCH;
+        self.keys_pressed_while_waiting = [false; 16];
+        self.cycles = 0;
+        self.rewind_buffer.clear();
+        self.input = [false; 16];
+        self.previous_input = [false; 16];
+        self.cpu_timer = Duration::ZERO;
+        self.sound_timer = Duration::ZERO;
+        self.delay_timer = Duration::ZERO;
+        self.waiting_for_vblank = false;
+        self.halted = false;
+        self.last_error = None;
+        self.resume_from_breakpoint = false;
+    }
+
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            cpu: self.cpu,
+            memory: self.memory.to_vec(),
+            active_pixels: self.active_pixels.clone(),
+            second_plane: self.second_plane.clone(),
+            input: self.input,
+            selected_planes: self.selected_planes,
+            sound_pattern: self.sound_pattern,
+            pitch: self.pitch,
+            keys_pressed_while_waiting: self.keys_pressed_while_waiting,
+            cpu_timer: self.cpu_timer,
+            sound_timer: self.sound_timer,
+            delay_timer: self.delay_timer,
+        }
+    }
+
+    /// Restores `state`, unless its memory is not 4KB or 64KB or does not reach the
+    /// load address.
+    pub fn load_state(&mut self, state: &SaveState) -> Result<(), Chip8Error> {
+        state.check_memory_size()?;
+        if state.memory.len() <= self.load_address {
+            return Err(SaveState::invalid(format!(
+                "memory of {} bytes ends before load address {:#06x}",
+                state.memory.len(),
+                self.load_address
+            )));
+        }
+
+        self.cpu = state.cpu;
+        self.memory = state.memory.clone().into_boxed_slice();
+        self.active_pixels = state.active_pixels.clone();
+        self.second_plane = state.second_plane.clone();
+        self.input = state.input;
+        self.selected_planes = state.selected_planes;
+        self.sound_pattern = state.sound_pattern;
+        self.pitch = state.pitch;
+        self.keys_pressed_while_waiting = state.keys_pressed_while_waiting;
+        self.cpu_timer = state.cpu_timer;
+        self.sound_timer = state.sound_timer;
+        self.delay_timer = state.delay_timer;
+        self.waiting_for_vblank = false;
+        Ok(())
+    }
+
+    /// Like `reset`, but also restores program memory to the last loaded program.
+    pub fn reset_and_reload(&mut self) {
+        self.reset();
+        self.memory[self.load_address..].fill(0);
+        // NOTE(panmar): The load address or memory size may have changed since the
+        // program was loaded, so only the part that still fits is restored
+        let len = self.rom.len().min(self.memory.len() - self.load_address);
+        self.memory[self.load_address..self.load_address + len].copy_from_slice(&self.rom[..len]);
+    }
+
+    /// Replaces the running program with a new one from a clean machine state.
+    /// On error the current program is left untouched.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reset_and_load_program_from_file<P: AsRef<Path>>(
+        &mut self,
+        filepath: P,
+    ) -> Result<(), Chip8Error> {
+        self.reset_and_load_program_from_data(&fs::read(filepath)?)
+    }
+
+    pub fn reset_and_load_program_from_data(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        self.check_program(data)?;
+
+        self.reset();
+        self.memory[self.load_address..].fill(0);
+        self.load_program_from_data(data)
+    }
+
+    pub fn program_counter(&self) -> u16 {
+        self.cpu.program_counter
+    }
+
+    /// Coordinates of the pixels lit on the first plane, row by row from the top
+    /// and left to right within a row.
+    pub fn active_pixels_sorted(&self) -> Vec<(u32, u32)> {
+        self.active_pixels.iter().collect()
+    }
+
+    /// Value of register V`index`. Panics unless `index` is below 16.
+    ///
+    /// ```
+    /// use chip8_emulator::chip8::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_program_from_data(&[0x6A, 0x42]).unwrap();
+    /// emulator.step_instruction();
+    /// assert_eq!(emulator.register(0xA), 0x42);
+    /// ```
+    pub fn register(&self, index: usize) -> u8 {
+        self.cpu.registers[index]
+    }
+
+    /// Sets register V`index`. Panics unless `index` is below 16.
+    pub fn set_register(&mut self, index: usize, value: u8) {
+        self.cpu.registers[index] = value;
+    }
+
+    /// Memory in `range`. Panics if it extends past the end of memory.
+    ///
+    /// ```
+    /// use chip8_emulator::chip8::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_program_from_data(&[0x12, 0x34, 0x56]).unwrap();
+    /// assert_eq!(emulator.memory_slice(0x200..0x203), &[0x12, 0x34, 0x56]);
+    /// ```
+    pub fn memory_slice(&self, range: Range<usize>) -> &[u8] {
+        &self.memory[range]
+    }
+
+    /// A copy of the registers, stack and timers as they are now.
+    ///
+    /// ```
+    /// use chip8_emulator::chip8::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_program_from_data(&[0xA1, 0x23]).unwrap();
+    /// let before = emulator.cpu_snapshot();
+    /// emulator.step_instruction();
+    /// assert_eq!(before.register_i, 0x000);
+    /// assert_eq!(emulator.cpu_snapshot().register_i, 0x123);
+    /// assert_eq!(emulator.cpu_snapshot().program_counter, 0x202);
+    /// ```
+    pub fn cpu_snapshot(&self) -> Cpu {
+        self.cpu
+    }
+
+    /// Sets the address of the next instruction to execute, masked to the 12-bit
+    /// address space. Fails if a whole instruction would not fit at that address.
+    pub fn set_pc(&mut self, address: u16) -> Result<(), Chip8Error> {
+        let address = address & ADDRESS_MASK;
+        if address as usize + 1 >= self.memory.len() {
+            return Err(Chip8Error::AddressOutOfBounds(address));
+        }
+        if address & 1 != 0 {
+            self.log(
+                LogLevel::Warn,
+                &format!("program counter set to odd address {:#06x}", address),
+            );
+        }
+
+        self.cpu.program_counter = address;
+        Ok(())
+    }
+
+    /// The display packed 8 pixels per byte, row-major, with the leftmost pixel in
+    /// the most significant bit, matching how display RAM is laid out in memory.
+    pub fn frame_buffer(&self) -> Vec<u8> {
+        let bytes_per_row = (self.active_pixels.width() / 8) as usize;
+        let mut bytes = Vec::with_capacity(bytes_per_row * self.active_pixels.height() as usize);
+        for row in self.active_pixels.rows() {
+            bytes.extend_from_slice(&row.to_be_bytes()[..bytes_per_row]);
+        }
+        bytes
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.active_pixels.resolution()
+    }
+
+    /// The first plane as text, one line per row with `#` for lit pixels and spaces
+    /// for the others, e.g. to print or compare in tests.
+    pub fn display_to_ascii(&self) -> String {
+        let mut ascii = String::new();
+        for y in 0..self.active_pixels.height() {
+            for x in 0..self.active_pixels.width() {
+                let lit = self.active_pixels.contains(&(x, y));
+                ascii.push(if lit { '#' } else { ' ' });
             }
-            LoadRegisters { last_register } => {
-                for i in 0..=last_register {
-                    self.cpu.registers[i] = self.memory[self.cpu.register_i as usize + i];
-                }
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    /// The registers, timers and display as a JSON object, for external debuggers.
+    pub fn to_json(&self) -> String {
+        let width = self.active_pixels.width() as usize;
+        let display = self
+            .active_pixels
+            .rows()
+            .iter()
+            .map(|row| {
+                format!(
+                    "{:0w$X}",
+                    row >> (u128::BITS as usize - width),
+                    w = width / 4
+                )
+            })
+            .collect();
+        let state = JsonState {
+            cpu: &self.cpu,
+            resolution: self.active_pixels.resolution(),
+            display,
+        };
+        serde_json::to_string(&state).expect("machine state serializes to JSON")
+    }
+
+    /// The display as a row-major RGBA image, each pixel a `scale`-sized square.
+    pub fn to_rgba(&self, scale: u32) -> Vec<u8> {
+        self.to_rgba_with_palette(scale, &DEFAULT_RGBA_PALETTE)
+    }
+
+    pub fn to_rgba_with_palette(&self, scale: u32, palette: &[[u8; 4]; 4]) -> Vec<u8> {
+        let (width, height) = (self.active_pixels.width(), self.active_pixels.height());
+        let mut rgba = Vec::with_capacity((width * height * scale * scale * 4) as usize);
+        for y in 0..height * scale {
+            for x in 0..width * scale {
+                let pixel = (x / scale, y / scale);
+                let color = palette_index(&self.active_pixels, &self.second_plane, pixel);
+                rgba.extend_from_slice(&palette[color]);
             }
+        }
+        rgba
+    }
 
-            Unknown { opcode } => {
-                println!("Unknown instruction: {:#06x}", opcode)
+    /// The pressed keys as a bitmask, where bit `k` is set while key `k` is down.
+    pub fn keypad(&self) -> u16 {
+        let mut keypad = 0;
+        for (key, pressed) in self.input.iter().enumerate() {
+            if *pressed {
+                keypad |= 1 << key;
             }
         }
+        keypad
+    }
 
-        return InstructionExecuteStatus::Complete;
+    pub fn set_keypad(&mut self, keypad: u16) {
+        for (key, pressed) in self.input.iter_mut().enumerate() {
+            *pressed = keypad & (1 << key) != 0;
+        }
     }
 
-    fn draw_pixels(&mut self, pixels: &[(u32, u32)]) -> bool {
-        let mut xored = false;
-        for pixel in pixels.iter() {
-            if self.active_pixels.contains(pixel) {
-                self.active_pixels.remove(pixel);
-                xored = true;
-            } else {
-                self.active_pixels.insert(*pixel);
-            }
+    /// Marks hex key `key` as held down. Panics unless `key` is below 16.
+    pub fn press_key(&mut self, key: u8) {
+        self.input[Emulator::key_index(key)] = true;
+    }
+
+    /// Marks hex key `key` as released. Panics unless `key` is below 16.
+    pub fn release_key(&mut self, key: u8) {
+        self.input[Emulator::key_index(key)] = false;
+    }
+
+    pub fn is_key_down(&self, key: u8) -> bool {
+        self.input[Emulator::key_index(key)]
+    }
+
+    /// The lowest key held down, if any.
+    pub fn any_key_pressed(&self) -> Option<u8> {
+        (0..16).find(|&key| self.input[key as usize])
+    }
+
+    /// Keys held down now that were up at the end of the last `step`.
+    pub fn newly_pressed(&self) -> Vec<u8> {
+        (0..16)
+            .filter(|&key| self.input[key as usize] && !self.previous_input[key as usize])
+            .collect()
+    }
+
+    /// Keys up now that were held down at the end of the last `step`.
+    pub fn newly_released(&self) -> Vec<u8> {
+        (0..16)
+            .filter(|&key| !self.input[key as usize] && self.previous_input[key as usize])
+            .collect()
+    }
+
+    fn key_index(key: u8) -> usize {
+        assert!(key < 16, "key {:#X} is not on the hex keypad", key);
+        key as usize
+    }
+
+    /// Applies the quirk settings of `preset`. Quirks only affect instructions
+    /// executed afterwards, so this is safe to call on a running program. Memory is
+    /// left as is; see `QuirkPreset::uses_wide_memory` and `set_wide_memory`.
+    pub fn set_quirk_preset(&mut self, preset: QuirkPreset) {
+        self.quirk_preset = Some(preset);
+        self.display_wait_quirk = preset == QuirkPreset::CosmacVip;
+        self.address_carry_quirk = false;
+        self.logic_vf_reset_quirk = preset == QuirkPreset::CosmacVip;
+        self.shift_quirk = match preset {
+            QuirkPreset::CosmacVip | QuirkPreset::XoChip => ShiftQuirk::CopyFromVy,
+            QuirkPreset::Chip48 | QuirkPreset::SuperChip => ShiftQuirk::InPlace,
+        };
+        self.jump_quirk = match preset {
+            QuirkPreset::CosmacVip | QuirkPreset::XoChip => JumpQuirk::V0Offset,
+            QuirkPreset::Chip48 | QuirkPreset::SuperChip => JumpQuirk::VxOffset,
+        };
+        self.memory_increment_quirk = match preset {
+            QuirkPreset::CosmacVip | QuirkPreset::XoChip => MemoryIncrementQuirk::IncrementByXPlus1,
+            QuirkPreset::Chip48 => MemoryIncrementQuirk::IncrementByX,
+            QuirkPreset::SuperChip => MemoryIncrementQuirk::Unchanged,
+        };
+        self.wrap_mode = match preset {
+            QuirkPreset::XoChip => WrapMode::Wrap,
+            _ => WrapMode::Clip,
+        };
+        self.scroll_quirk = match preset {
+            QuirkPreset::SuperChip => ScrollQuirk::HalvedInLowResolution,
+            _ => ScrollQuirk::ScreenPixels,
+        };
+        self.collision_quirk = match preset {
+            QuirkPreset::SuperChip => CollisionQuirk::CountRows,
+            _ => CollisionQuirk::Flag,
+        };
+    }
+
+    /// The most recently applied preset, if any.
+    pub fn quirk_preset(&self) -> Option<QuirkPreset> {
+        self.quirk_preset
+    }
+
+    /// Grows memory to XO-CHIP's 64KB, or shrinks it back to 4KB dropping anything
+    /// past the end. A running program should be restarted after shrinking, as its
+    /// program counter, I and stack may point past the new end.
+    pub fn set_wide_memory(&mut self, enabled: bool) {
+        let size = if enabled {
+            XO_CHIP_MEMORY_SIZE
+        } else {
+            MEMORY_SIZE
+        };
+        self.set_memory_size(size);
+    }
+
+    pub fn has_wide_memory(&self) -> bool {
+        self.memory.len() == XO_CHIP_MEMORY_SIZE
+    }
+
+    /// Reallocates memory keeping its contents up to the smaller of both sizes. A
+    /// load address past the new end goes back to the default one.
+    fn set_memory_size(&mut self, size: usize) {
+        let mut memory = vec![0; size].into_boxed_slice();
+        let kept = size.min(self.memory.len());
+        memory[..kept].copy_from_slice(&self.memory[..kept]);
+        self.memory = memory;
+        if self.load_address >= size {
+            self.log(
+                LogLevel::Warn,
+                &format!(
+                    "load address {:#06x} is past the end of memory, using {:#06x}",
+                    self.load_address, PROGRAM_ADDRESS
+                ),
+            );
+            self.load_address = PROGRAM_ADDRESS;
         }
-        return xored;
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use assert_hex::assert_eq_hex;
+    pub fn set_shift_quirk(&mut self, shift_quirk: ShiftQuirk) {
+        self.shift_quirk = shift_quirk;
+    }
 
-    #[test]
-    #[rustfmt::skip]
-    fn should_convert_from_instruction_to_opcode() {
-        use Instruction::*;
-        assert_eq_hex!(ClearDisplay.to_opcode(), 0x00E0);
-        assert_eq_hex!(Return.to_opcode(), 0x00EE);
-        assert_eq_hex!(Jump{address: 0x04F1}.to_opcode(), 0x14F1);
-        assert_eq_hex!(Call{address: 0x07AB}.to_opcode(), 0x27AB);
-        assert_eq_hex!(SkipIfRegEqConstant{register: 0xA, constant: 0xC3}.to_opcode(), 0x3AC3);
-        assert_eq_hex!(SkipIfRegNotEqConstant{register: 1, constant: 0x23}.to_opcode(), 0x4123);
-        assert_eq_hex!(SkipIfRegEqReg{register_lhs: 0xA, register_rhs: 0xD}.to_opcode(), 0x5AD0);
-        assert_eq_hex!(SetRegToConstant{register: 7, constant: 0xAF}.to_opcode(), 0x67AF);
-        assert_eq_hex!(AddConstToReg{register: 0xC, constant: 0x42}.to_opcode(), 0x7C42);
-        assert_eq_hex!(SetRegToReg{register_lhs: 0x9, register_rhs: 0x3}.to_opcode(), 0x8930);
-        assert_eq_hex!(BitwiseOr{register_lhs: 0x5, register_rhs: 0xF}.to_opcode(), 0x85F1);
-        assert_eq_hex!(BitwiseAnd{register_lhs: 0x5, register_rhs: 0xF}.to_opcode(), 0x85F2);
-        assert_eq_hex!(BitwiseXor{register_lhs: 0x5, register_rhs: 0xF}.to_opcode(), 0x85F3);
-        assert_eq_hex!(AddRegToReg{register_lhs: 0x6, register_rhs: 0x0}.to_opcode(), 0x8604);
-        assert_eq_hex!(SubReg2FromReg1{register_lhs: 0xA, register_rhs: 0xB}.to_opcode(), 0x8AB5);
+    pub fn set_jump_quirk(&mut self, jump_quirk: JumpQuirk) {
+        self.jump_quirk = jump_quirk;
+    }
 
-        // TODO(panmar): There is some ambiguity about this instruction;
-        // Y register (3rd digit) seems to be unused
-        assert_eq_hex!(BitwiseShrBy1{register: 0x9}.to_opcode(), 0x8906);
+    pub fn set_memory_increment_quirk(&mut self, memory_increment_quirk: MemoryIncrementQuirk) {
+        self.memory_increment_quirk = memory_increment_quirk;
+    }
 
-        assert_eq_hex!(SubReg1FromReg2{register_lhs: 0xA, register_rhs: 0xB}.to_opcode(), 0x8AB7);
+    pub fn set_wrap_mode(&mut self, wrap_mode: WrapMode) {
+        self.wrap_mode = wrap_mode;
+    }
 
-        // TODO(panmar): There is some ambiguity about this instruction;
-        // Y register (3rd digit) seems to be unused
-        assert_eq_hex!(BitwiseShlBy1{register: 0x9}.to_opcode(), 0x890E);
+    pub fn set_unknown_opcode_policy(&mut self, unknown_opcode_policy: UnknownOpcodePolicy) {
+        self.unknown_opcode_policy = unknown_opcode_policy;
+    }
 
-        assert_eq_hex!(CondRegNotEqReg{register_lhs: 0xA, register_rhs: 0xB}.to_opcode(), 0x9AB0);
-        assert_eq_hex!(SetAddress{address: 0x123}.to_opcode(), 0xA123);
-        assert_eq_hex!(JumpWithV0Offset{address: 0x123}.to_opcode(), 0xB123);
+    pub fn set_scroll_quirk(&mut self, scroll_quirk: ScrollQuirk) {
+        self.scroll_quirk = scroll_quirk;
+    }
+
+    /// Bit 0 selects `active_pixels`, bit 1 `second_plane`.
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    pub fn set_collision_quirk(&mut self, collision_quirk: CollisionQuirk) {
+        self.collision_quirk = collision_quirk;
+    }
+
+    /// When enabled, `DisplaySprite` stalls the CPU until the next 60 Hz timer tick,
+    /// like the COSMAC VIP waiting for vertical blank.
+    pub fn set_display_wait_quirk(&mut self, enabled: bool) {
+        self.display_wait_quirk = enabled;
+    }
+
+    /// When enabled, `BitwiseOr`, `BitwiseAnd` and `BitwiseXor` reset VF to 0, like
+    /// the COSMAC VIP whose logic routines clobbered it.
+    pub fn set_logic_vf_reset_quirk(&mut self, enabled: bool) {
+        self.logic_vf_reset_quirk = enabled;
+    }
+
+    /// When enabled, `AddRegToAddressWithoutCarry` sets VF to whether I went past
+    /// 0xFFF, like the Amiga interpreter. Spacefight 2091! relies on it.
+    pub fn set_address_carry_quirk(&mut self, enabled: bool) {
+        self.address_carry_quirk = enabled;
+    }
+
+    /// Whether the sound timer is running, i.e. a frontend should be playing sound.
+    pub fn is_beeping(&self) -> bool {
+        self.cpu.sound_timer > 0
+    }
+
+    /// The XO-CHIP audio pattern, 128 one-bit samples played while the sound timer
+    /// runs. `None` until the program loads one, in which case a plain beep is played.
+    pub fn sound_pattern(&self) -> Option<&[u8; 16]> {
+        self.sound_pattern.as_ref()
+    }
+
+    /// Audio pattern bits played per second, as set by the XO-CHIP pitch register.
+    pub fn playback_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /// Instructions executed so far, counting every retry of an instruction that
+    /// waits, like `FX0A`.
+    pub fn cycle_count(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Writes a `<cycle> <keypad>` line, the keypad as a hex bitmask, to `writer`
+    /// whenever the input differs from the last recorded one before an instruction.
+    pub fn set_input_recorder<W: Write + 'static>(&mut self, writer: W) {
+        self.input_recorder = Some(Box::new(writer));
+        self.recorded_input = [false; 16];
+    }
+
+    /// Replaces `input` with each recorded input right before the instruction of its
+    /// cycle executes, ignoring input set in between.
+    pub fn replay_input(&mut self, recording: Vec<(u64, [bool; 16])>) {
+        self.input_replay = recording.into();
+    }
+
+    /// Calls `trace` before every instruction executes, e.g. to log execution.
+    pub fn set_trace(&mut self, trace: TraceFn) {
+        self.trace = Some(trace);
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.trace = None;
+    }
+
+    /// Keeps the state from before each of the last `depth` instructions, so that
+    /// `step_back` can undo them. A depth of 0, the default, turns rewinding off.
+    pub fn set_rewind_depth(&mut self, depth: usize) {
+        self.rewind_depth = depth;
+        while self.rewind_buffer.len() > depth {
+            self.rewind_buffer.pop_front();
+        }
+    }
+
+    /// Undoes the last executed instruction and returns whether there was one to
+    /// undo. Only instructions executed while rewinding was on can be undone.
+    pub fn step_back(&mut self) -> bool {
+        let Some(state) = self.rewind_buffer.pop_back() else {
+            return false;
+        };
+        if self.load_state(&state).is_err() {
+            return false;
+        }
+        self.cycles = self.cycles.saturating_sub(1);
+        self.halted = false;
+        self.last_error = None;
+        true
+    }
+
+    /// Runs `handler` on `0NNN` calls to machine code routines, which are ignored
+    /// otherwise like in most interpreters.
+    pub fn set_machine_code_handler(&mut self, handler: MachineCodeFn) {
+        self.machine_code_handler = Some(handler);
+    }
+
+    pub fn clear_machine_code_handler(&mut self) {
+        self.machine_code_handler = None;
+    }
+
+    /// Whether the CPU is stalled after a draw, waiting for the next 60 Hz tick.
+    pub fn is_waiting_for_vblank(&self) -> bool {
+        self.waiting_for_vblank
+    }
+
+    /// At `LogLevel::Debug`, runtime errors are followed by a disassembly of the
+    /// surrounding code.
+    pub fn set_log_level(&mut self, log_level: LogLevel) {
+        self.log_level = log_level;
+    }
+
+    fn log(&self, level: LogLevel, message: &str) {
+        if level > self.log_level {
+            return;
+        }
+        match level {
+            LogLevel::Error => eprintln!("Error: {}", message),
+            LogLevel::Warn => eprintln!("Warning: {}", message),
+            _ => eprintln!("{}", message),
+        }
+    }
+
+    fn log_error_at(&self, address: u16, message: &str) {
+        self.log(LogLevel::Error, message);
+        if self.log_level >= LogLevel::Debug {
+            for line in self.disassembly_context(address, 3) {
+                eprintln!("{}", line);
+            }
+        }
+    }
+
+    /// The big-endian opcode stored at `address`, if both of its bytes are in memory.
+    pub fn opcode_at(&self, address: u16) -> Option<u16> {
+        let address = address as usize;
+        if address + 1 >= self.memory.len() {
+            return None;
+        }
+        Some(u16::from_be_bytes([
+            self.memory[address],
+            self.memory[address + 1],
+        ]))
+    }
+
+    /// The instruction at the program counter, decoded without executing it.
+    pub fn peek_instruction(&self) -> Option<Instruction> {
+        self.opcode_at(self.cpu.program_counter)
+            .map(Instruction::decode)
+    }
+
+    /// Disassembles `radius` instructions before and after `address`, one line per
+    /// instruction, with the line at `address` marked by `>`.
+    pub fn disassembly_context(&self, address: u16, radius: usize) -> Vec<String> {
+        self.disassemble_around(address, radius)
+            .into_iter()
+            .filter_map(|(current, line)| {
+                let opcode = self.opcode_at(current)?;
+                let marker = if current == address { '>' } else { ' ' };
+                Some(format!(
+                    "{} {:#06x}: {:04X}  {}",
+                    marker, current, opcode, line
+                ))
+            })
+            .collect()
+    }
+
+    /// Disassembles up to `radius` instructions before and after `center`, e.g. the
+    /// program counter, as `(address, line)` pairs. The line of the instruction at
+    /// the program counter starts with `>`, the others with a space.
+    pub fn disassemble_window(&self, center: u16, radius: usize) -> Vec<(u16, String)> {
+        self.disassemble_around(center, radius)
+            .into_iter()
+            .map(|(address, line)| {
+                let marker = if address == self.cpu.program_counter {
+                    '>'
+                } else {
+                    ' '
+                };
+                (address, format!("{} {}", marker, line))
+            })
+            .collect()
+    }
+
+    fn disassemble_around(&self, center: u16, radius: usize) -> Vec<(u16, String)> {
+        let first = (center as usize).saturating_sub(2 * radius);
+        let end = (center as usize + 2 * radius + 2).min(self.memory.len());
+        if first >= end {
+            return Vec::new();
+        }
+        disasm::disassemble_at(&self.memory[first..end], first as u16)
+    }
+
+    /// Whether execution stopped, on an error or a jump to itself; `step` keeps
+    /// ticking timers but runs no instructions.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// The error that halted execution, if any.
+    pub fn last_error(&self) -> Option<&Chip8Error> {
+        self.last_error.as_ref()
+    }
+
+    fn halt_with_error(&mut self, address: u16, error: Chip8Error) {
+        self.log_error_at(address, &format!("{} at {:#06x}", error, address));
+        self.halted = true;
+        self.last_error = Some(error);
+    }
+
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    /// Number of times each instruction was executed while profiling was enabled.
+    pub fn instruction_histogram(&self) -> &HashMap<&'static str, u64> {
+        &self.instruction_histogram
+    }
+
+    pub fn set_cpu_clock(&mut self, cpu_clock: CpuClock) {
+        self.cpu_clock = cpu_clock;
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) {
+        self.breakpoints.remove(&address);
+    }
+
+    /// Makes `step` stop with `StepStatus::WatchHit` after an instruction changes
+    /// the memory cell at `address`.
+    pub fn add_memory_watch(&mut self, address: u16) {
+        self.memory_watches.insert(address);
+    }
+
+    pub fn remove_memory_watch(&mut self, address: u16) {
+        self.memory_watches.remove(&address);
+    }
+
+    /// Stores `value` at `address`, noting the first change to a watched cell.
+    fn write_memory(&mut self, address: usize, value: u8) {
+        let old_value = self.memory[address];
+        self.memory[address] = value;
+        let watched =
+            !self.memory_watches.is_empty() && self.memory_watches.contains(&(address as u16));
+        if watched && old_value != value && self.watch_hit.is_none() {
+            self.watch_hit = Some(MemoryWrite {
+                address: address as u16,
+                old_value,
+                new_value: value,
+            });
+        }
+    }
+
+    /// Like `step`, but fails with the error that halted execution during this call,
+    /// so callers can react to it. Each error is reported once, by the call that hit
+    /// it; `last_error` keeps it afterwards.
+    pub fn try_step(&mut self, elapsed_time: Duration) -> Result<StepStatus, Chip8Error> {
+        let was_halted = self.halted;
+        let status = self.step(elapsed_time);
+        match &self.last_error {
+            Some(error) if !was_halted => Err(error.clone()),
+            _ => Ok(status),
+        }
+    }
+
+    pub fn step(&mut self, elapsed_time: Duration) -> StepStatus {
+        let status = self.step_cpu(elapsed_time);
+        self.previous_input = self.input;
+        status
+    }
+
+    fn step_cpu(&mut self, elapsed_time: Duration) -> StepStatus {
+        self.tick_timers(elapsed_time);
+
+        if self.halted {
+            return StepStatus::Running;
+        }
+
+        // NOTE(panmar): `cpu_timer` counts down to the next instruction, so a long
+        // `elapsed_time` runs every instruction that became due during it
+        let mut remaining_time = elapsed_time.min(MAX_CPU_CATCH_UP);
+        let mut executed_instructions = 0;
+        while !self.halted && !self.waiting_for_vblank {
+            let due = match self.cpu_clock {
+                CpuClock::Hz(_) => remaining_time > self.cpu_timer,
+                CpuClock::Unthrottled => executed_instructions == 0,
+            };
+            if !due {
+                break;
+            }
+
+            if self.stop_at_breakpoint() {
+                return StepStatus::BreakpointHit(self.cpu.program_counter);
+            }
+
+            remaining_time -= self.cpu_timer;
+            self.cpu_timer = Duration::ZERO;
+            match self.fetch_and_execute() {
+                Some((_, InstructionExecuteStatus::Complete)) => {
+                    self.cpu_timer = self.instruction_interval();
+                    executed_instructions += 1;
+                    if let Some(write) = self.watch_hit.take() {
+                        return StepStatus::WatchHit(write);
+                    }
+                }
+                // NOTE(panmar): An instruction in progress is retried on the next step
+                _ => break,
+            }
+        }
+        self.cpu_timer = self.cpu_timer.saturating_sub(remaining_time);
+        StepStatus::Running
+    }
+
+    /// Counts the delay and sound timers down at 60 Hz over `elapsed_time` without
+    /// executing any instructions. `step` does this itself; use it together with
+    /// `step_instruction`, which leaves the timers alone. A tick also ends the wait
+    /// for vertical blank of the display wait quirk.
+    pub fn tick_timers(&mut self, elapsed_time: Duration) {
+        self.delay_timer = self.delay_timer.saturating_add(elapsed_time);
+        self.sound_timer = self.sound_timer.saturating_add(elapsed_time);
+
+        while self.delay_timer >= TIMER_INTERVAL {
+            self.cpu.delay_timer = self.cpu.delay_timer.saturating_sub(1);
+            self.delay_timer -= TIMER_INTERVAL;
+            self.waiting_for_vblank = false;
+        }
+
+        while self.sound_timer >= TIMER_INTERVAL {
+            self.cpu.sound_timer = self.cpu.sound_timer.saturating_sub(1);
+            self.sound_timer -= TIMER_INTERVAL;
+        }
+    }
+
+    fn instruction_interval(&self) -> Duration {
+        match self.cpu_clock {
+            CpuClock::Hz(hz) => Duration::from_secs(1) / hz.max(1),
+            CpuClock::Unthrottled => Duration::ZERO,
+        }
+    }
+
+    /// Executes exactly one instruction regardless of the CPU clock and returns it, or
+    /// `None` when halted. Timers only advance in `step` and `tick_timers`.
+    pub fn step_instruction(&mut self) -> Option<Instruction> {
+        self.fetch_and_execute().map(|(instruction, _)| instruction)
+    }
+
+    /// Like `step_instruction`, but a `Call` runs until the subroutine returns to the
+    /// instruction after it, as if with a temporary breakpoint there. Recursive calls
+    /// returning to the same address do not stop it, the stack has to be back at the
+    /// depth of the call. Breakpoints inside the subroutine still stop it early.
+    pub fn step_over(&mut self) -> Option<Instruction> {
+        let call_address = self.cpu.program_counter;
+        let stack_index = self.cpu.stack_index;
+        let instruction = self.step_instruction()?;
+        if let Instruction::Call { .. } = instruction {
+            let return_address = ((call_address as usize + 2) % self.memory.len()) as u16;
+            let mut executed_instructions = 0;
+            while (self.cpu.program_counter, self.cpu.stack_index) != (return_address, stack_index)
+                && executed_instructions < MAX_STEP_OVER_INSTRUCTIONS
+                && self.run_instructions(1) == 1
+            {
+                executed_instructions += 1;
+            }
+        }
+        Some(instruction)
+    }
+
+    /// Executes up to `n` instructions regardless of the CPU clock, stopping early
+    /// when halted or before an instruction with a breakpoint. Returns how many were
+    /// executed. Like `step_instruction`, it leaves the timers alone.
+    pub fn run_instructions(&mut self, n: usize) -> usize {
+        let mut executed_instructions = 0;
+        while executed_instructions < n && !self.stop_at_breakpoint() {
+            if self.fetch_and_execute().is_none() {
+                break;
+            }
+            executed_instructions += 1;
+        }
+        executed_instructions
+    }
+
+    /// Whether execution should stop before the next instruction. A breakpoint only
+    /// stops it once, the next call resumes past it.
+    fn stop_at_breakpoint(&mut self) -> bool {
+        let address = self.cpu.program_counter;
+        if self.breakpoints.contains(&address) && !self.resume_from_breakpoint {
+            self.resume_from_breakpoint = true;
+            return true;
+        }
+        self.resume_from_breakpoint = false;
+        false
+    }
+
+    fn fetch_and_execute(&mut self) -> Option<(Instruction, InstructionExecuteStatus)> {
+        if self.halted {
+            return None;
+        }
+        let Some(opcode) = self.fetch_opcode() else {
+            let address = self.cpu.program_counter;
+            self.halt_with_error(address, Chip8Error::AddressOutOfBounds(address));
+            return None;
+        };
+        // NOTE(panmar): Skipping the hooks as a whole keeps the common case fast
+        if self.has_instruction_hooks() {
+            if self.rewind_depth > 0 {
+                if self.rewind_buffer.len() == self.rewind_depth {
+                    self.rewind_buffer.pop_front();
+                }
+                self.rewind_buffer.push_back(self.save_state());
+            }
+            self.replay_and_record_input();
+            if let Some(trace) = &mut self.trace {
+                trace(self.cpu.program_counter, opcode, &self.cpu);
+            }
+        }
+        let instruction_address = self.cpu.program_counter;
+        self.watch_hit = None;
+        let instruction = Instruction::decode(opcode);
+        let status = self.execute(instruction);
+        let misaligned = !self.cpu.program_counter.is_multiple_of(2);
+        if self.strict_alignment && misaligned && !self.halted {
+            let error = Chip8Error::MisalignedProgramCounter(self.cpu.program_counter);
+            self.halt_with_error(instruction_address, error);
+        }
+        Some((instruction, status))
+    }
+
+    fn has_instruction_hooks(&self) -> bool {
+        self.trace.is_some()
+            || self.input_recorder.is_some()
+            || !self.input_replay.is_empty()
+            || self.rewind_depth > 0
+    }
+
+    fn replay_and_record_input(&mut self) {
+        while let Some(&(cycle, input)) = self.input_replay.front() {
+            if cycle > self.cycles {
+                break;
+            }
+            self.input = input;
+            self.input_replay.pop_front();
+        }
+
+        if self.input_recorder.is_none() || self.input == self.recorded_input {
+            return;
+        }
+        let line = format!("{} {:04X}", self.cycles, self.keypad());
+        if let Some(recorder) = &mut self.input_recorder {
+            if let Err(e) = writeln!(recorder, "{}", line) {
+                self.input_recorder = None;
+                self.log(LogLevel::Warn, &format!("input recording stopped: {}", e));
+            }
+        }
+        self.recorded_input = self.input;
+    }
+
+    fn fetch_opcode(&mut self) -> Option<u16> {
+        self.opcode_at(self.cpu.program_counter)
+    }
+
+    /// Maps an address computed from I onto memory, wrapping around past its end.
+    fn memory_address(&self, address: usize) -> usize {
+        address % self.memory.len()
+    }
+
+    /// Moves the program counter to the next instruction, wrapping around at the end
+    /// of memory.
+    fn advance_program_counter(&mut self) {
+        let address = self.cpu.program_counter as usize + 2;
+        self.cpu.program_counter = (address % self.memory.len()) as u16;
+    }
+
+    /// Moves the program counter past the next instruction, which takes 4 bytes
+    /// for XO-CHIP's `F000 NNNN`.
+    fn skip_next_instruction(&mut self) {
+        let is_long = self.opcode_at(self.cpu.program_counter) == Some(0xF000);
+        self.advance_program_counter();
+        if is_long {
+            self.advance_program_counter();
+        }
+    }
+
+    fn reset_vf_after_logic(&mut self) {
+        if self.logic_vf_reset_quirk {
+            self.cpu.registers[0xF] = 0;
+        }
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> InstructionExecuteStatus {
+        self.cycles += 1;
+        if self.profiling {
+            *self
+                .instruction_histogram
+                .entry(instruction.name())
+                .or_insert(0) += 1;
+        }
+
+        let instruction_address = self.cpu.program_counter;
+        self.advance_program_counter();
+
+        use Instruction::*;
+        match instruction {
+            ClearDisplay => {
+                self.for_each_selected_plane(|pixels| pixels.clear());
+            }
+            Return => {
+                if self.cpu.stack_index < 0 {
+                    self.cpu.program_counter = instruction_address;
+                    self.halt_with_error(instruction_address, Chip8Error::StackUnderflow);
+                    return InstructionExecuteStatus::Complete;
+                }
+                self.cpu.program_counter = self.cpu.stack[self.cpu.stack_index as usize];
+                self.cpu.stack_index -= 1;
+            }
+            Jump { address } => {
+                // NOTE(panmar): Jumping to itself is how most programs end, there is
+                // no way out of it, so stop executing instead of spinning forever
+                if address == instruction_address {
+                    self.log(
+                        LogLevel::Debug,
+                        &format!("halted on jump to itself at {:#06x}", address),
+                    );
+                    self.halted = true;
+                }
+                self.cpu.program_counter = address;
+            }
+            Call { address } => {
+                if self.cpu.stack_index + 1 >= self.cpu.stack.len() as i8 {
+                    self.cpu.program_counter = instruction_address;
+                    self.halt_with_error(instruction_address, Chip8Error::StackOverflow);
+                    return InstructionExecuteStatus::Complete;
+                }
+                self.cpu.stack_index += 1;
+                self.cpu.stack[self.cpu.stack_index as usize] = self.cpu.program_counter;
+                self.cpu.program_counter = address;
+            }
+            SkipIfRegEqConstant { register, constant } => {
+                if self.cpu.registers[register] == constant {
+                    self.skip_next_instruction();
+                }
+            }
+            SkipIfRegNotEqConstant { register, constant } => {
+                if self.cpu.registers[register] != constant {
+                    self.skip_next_instruction();
+                }
+            }
+            SkipIfRegEqReg {
+                register_lhs,
+                register_rhs,
+            } => {
+                if self.cpu.registers[register_lhs] == self.cpu.registers[register_rhs] {
+                    self.skip_next_instruction();
+                }
+            }
+            SetRegToConstant { register, constant } => self.cpu.registers[register] = constant,
+            AddConstToReg { register, constant } => {
+                self.cpu.registers[register] = self.cpu.registers[register].wrapping_add(constant);
+            }
+            SetRegToReg {
+                register_lhs,
+                register_rhs,
+            } => self.cpu.registers[register_lhs] = self.cpu.registers[register_rhs],
+            BitwiseOr {
+                register_lhs,
+                register_rhs,
+            } => {
+                self.cpu.registers[register_lhs] |= self.cpu.registers[register_rhs];
+                self.reset_vf_after_logic();
+            }
+            BitwiseAnd {
+                register_lhs,
+                register_rhs,
+            } => {
+                self.cpu.registers[register_lhs] &= self.cpu.registers[register_rhs];
+                self.reset_vf_after_logic();
+            }
+            BitwiseXor {
+                register_lhs,
+                register_rhs,
+            } => {
+                self.cpu.registers[register_lhs] ^= self.cpu.registers[register_rhs];
+                self.reset_vf_after_logic();
+            }
+            AddRegToReg {
+                register_lhs,
+                register_rhs,
+            } => {
+                let (sum, overflow) = self.cpu.registers[register_lhs]
+                    .overflowing_add(self.cpu.registers[register_rhs]);
+                self.cpu.registers[register_lhs] = sum;
+                self.cpu.registers[0xF] = overflow as u8;
+            }
+            SubReg2FromReg1 {
+                register_lhs,
+                register_rhs,
+            } => {
+                let (sub, borrow) = self.cpu.registers[register_lhs]
+                    .overflowing_sub(self.cpu.registers[register_rhs]);
+                self.cpu.registers[register_lhs] = sub;
+                self.cpu.registers[0xF] = !borrow as u8;
+            }
+            BitwiseShrBy1 {
+                register_lhs,
+                register_rhs,
+            } => {
+                if self.shift_quirk == ShiftQuirk::CopyFromVy {
+                    self.cpu.registers[register_lhs] = self.cpu.registers[register_rhs];
+                }
+                let shifted_out = self.cpu.registers[register_lhs] & 1;
+                self.cpu.registers[register_lhs] >>= 1;
+                self.cpu.registers[0xF] = shifted_out;
+            }
+            SubReg1FromReg2 {
+                register_lhs,
+                register_rhs,
+            } => {
+                let (sub, borrow) = self.cpu.registers[register_rhs]
+                    .overflowing_sub(self.cpu.registers[register_lhs]);
+                self.cpu.registers[register_lhs] = sub;
+                self.cpu.registers[0xF] = !borrow as u8;
+            }
+            BitwiseShlBy1 {
+                register_lhs,
+                register_rhs,
+            } => {
+                if self.shift_quirk == ShiftQuirk::CopyFromVy {
+                    self.cpu.registers[register_lhs] = self.cpu.registers[register_rhs];
+                }
+                let shifted_out = self.cpu.registers[register_lhs] >> 7;
+                self.cpu.registers[register_lhs] <<= 1;
+                self.cpu.registers[0xF] = shifted_out;
+            }
+            CondRegNotEqReg {
+                register_lhs,
+                register_rhs,
+            } => {
+                if self.cpu.registers[register_lhs] != self.cpu.registers[register_rhs] {
+                    self.skip_next_instruction();
+                }
+            }
+            SetAddress { address } => self.cpu.register_i = address,
+            JumpWithV0Offset { address } => {
+                let base_register = match self.jump_quirk {
+                    JumpQuirk::V0Offset => 0,
+                    JumpQuirk::VxOffset => (address >> 8) as usize,
+                };
+                self.cpu.program_counter =
+                    (self.cpu.registers[base_register] as u16 + address) & ADDRESS_MASK;
+            }
+            BitwiseAndWithRand { register, constant } => {
+                let random_number: u8 = self.rng.gen();
+                self.cpu.registers[register] = constant & random_number;
+            }
+            DisplaySprite {
+                register_x,
+                register_y,
+                n_bytes,
+            } => {
+                let (width, height) = (self.active_pixels.width(), self.active_pixels.height());
+                let origin_x = self.cpu.registers[register_x] as u32 % width;
+                let origin_y = self.cpu.registers[register_y] as u32 % height;
+                // NOTE(panmar): DXY0 draws a 16x16 sprite, two bytes per row
+                let (sprite_width, sprite_height) =
+                    if n_bytes == 0 { (16, 16) } else { (8, n_bytes) };
+                let bytes_per_row = sprite_width as usize / 8;
+                let row_mask = self.active_pixels.row_mask();
+                let sprite_size = sprite_height * bytes_per_row;
+                // NOTE(panmar): With both planes selected the sprite holds the rows of the
+                // first plane followed by the rows of the second
+                let selected_planes = self.selected_planes;
+                let planes = (0..2).filter(move |plane| selected_planes & (1 << plane) != 0);
+
+                let mut collided_rows = 0;
+                let mut clipped_rows = 0;
+                for i in 0..sprite_height {
+                    let mut row_y = origin_y + i as u32;
+                    if row_y >= height {
+                        match self.wrap_mode {
+                            WrapMode::Clip => {
+                                clipped_rows = sprite_height - i;
+                                break;
+                            }
+                            WrapMode::Wrap => row_y %= height,
+                        }
+                    }
+
+                    let mut row_collided = false;
+                    for (k, plane) in planes.clone().enumerate() {
+                        let row_address =
+                            self.cpu.register_i as usize + k * sprite_size + i * bytes_per_row;
+                        let mut sprite_row = 0u128;
+                        for j in 0..bytes_per_row {
+                            let address = self.memory_address(row_address + j);
+                            sprite_row = (sprite_row << 8) | self.memory[address] as u128;
+                        }
+                        let sprite_at_left_edge = sprite_row << (u128::BITS - sprite_width);
+                        let mut row_bits = (sprite_at_left_edge >> origin_x) & row_mask;
+                        if self.wrap_mode == WrapMode::Wrap && origin_x + sprite_width > width {
+                            row_bits |= (sprite_at_left_edge << (width - origin_x)) & row_mask;
+                        }
+                        row_collided |= self.plane_mut(plane).xor_row(row_y, row_bits);
+                    }
+                    if row_collided {
+                        collided_rows += 1;
+                    }
+                }
+
+                self.cpu.registers[0xF] = match (self.collision_quirk, self.resolution()) {
+                    (CollisionQuirk::CountRows, Resolution::High) => {
+                        (collided_rows + clipped_rows) as u8
+                    }
+                    _ => (collided_rows > 0) as u8,
+                };
+
+                if self.display_wait_quirk {
+                    self.waiting_for_vblank = true;
+                }
+            }
+            // NOTE(panmar): The keypad only has 16 keys, so only the low nibble counts
+            SkipIfKeyPressed { register } => {
+                let key = self.cpu.registers[register] & 0x0F;
+                if self.input[key as usize] {
+                    self.skip_next_instruction();
+                }
+            }
+            SkipIfKeyNotPressed { register } => {
+                let key = self.cpu.registers[register] & 0x0F;
+                if !self.input[key as usize] {
+                    self.skip_next_instruction();
+                }
+            }
+            SetRegToDelayTimer { register } => self.cpu.registers[register] = self.cpu.delay_timer,
+            AwaitAndSetKeyPress { register } => {
+                // NOTE(panmar): Completes once a key pressed during the wait is released,
+                // so a held key does not fire again on every FX0A
+                let released_key =
+                    (0..16).find(|&key| self.keys_pressed_while_waiting[key] && !self.input[key]);
+                match released_key {
+                    Some(key) => {
+                        self.cpu.registers[register] = key as u8;
+                        self.keys_pressed_while_waiting = [false; 16];
+                    }
+                    None => {
+                        for (pressed, input) in
+                            self.keys_pressed_while_waiting.iter_mut().zip(self.input)
+                        {
+                            *pressed |= input;
+                        }
+                        self.cpu.program_counter = instruction_address;
+                        return InstructionExecuteStatus::InProgress;
+                    }
+                }
+            }
+            SetDelayTimer { register } => self.cpu.delay_timer = self.cpu.registers[register],
+            SetSoundTimer { register } => self.cpu.sound_timer = self.cpu.registers[register],
+            AddRegToAddressWithoutCarry { register } => {
+                self.cpu.register_i = self
+                    .cpu
+                    .register_i
+                    .wrapping_add(self.cpu.registers[register] as u16);
+                if self.address_carry_quirk {
+                    self.cpu.registers[0xF] = (self.cpu.register_i > ADDRESS_MASK) as u8;
+                }
+            }
+            SetAddressOfFontChar { register } => {
+                let character = (self.cpu.registers[register] & 0xF) as usize;
+                self.cpu.register_i = (FONT_ADDRESS + character * FONT_SPRITE_SIZE) as u16;
+            }
+            SetAddressOfBigFontChar { register } => {
+                let character = (self.cpu.registers[register] & 0xF) as usize;
+                self.cpu.register_i = (BIG_FONT_ADDRESS + character * BIG_FONT_SPRITE_SIZE) as u16;
+            }
+            SetLongAddress => {
+                // NOTE(panmar): The address is the word following the opcode
+                let address = self.cpu.program_counter as usize;
+                self.cpu.register_i = u16::from_be_bytes([
+                    self.memory[self.memory_address(address)],
+                    self.memory[self.memory_address(address + 1)],
+                ]);
+                self.advance_program_counter();
+            }
+            StoreRegBcd { register } => {
+                // NOTE(panmar): Wrapping would put the ones digit over the font at the
+                // start of memory, so a store running past the end halts instead
+                let last_address = self.cpu.register_i as usize + 2;
+                if last_address >= self.memory.len() {
+                    self.cpu.program_counter = instruction_address;
+                    let error = Chip8Error::AddressOutOfBounds(self.cpu.register_i);
+                    self.halt_with_error(instruction_address, error);
+                    return InstructionExecuteStatus::Complete;
+                }
+                let mut value = self.cpu.registers[register];
+                for offset in (0..3).rev() {
+                    let address = self.cpu.register_i as usize + offset;
+                    self.write_memory(address, value % 10);
+                    value /= 10;
+                }
+            }
+            StoreRegisters { last_register } => {
+                for i in 0..=last_register {
+                    let address = self.memory_address(self.cpu.register_i as usize + i);
+                    self.write_memory(address, self.cpu.registers[i]);
+                }
+                self.apply_memory_increment_quirk(last_register);
+            }
+            LoadRegisters { last_register } => {
+                for i in 0..=last_register {
+                    let address = self.memory_address(self.cpu.register_i as usize + i);
+                    self.cpu.registers[i] = self.memory[address];
+                }
+                self.apply_memory_increment_quirk(last_register);
+            }
+
+            ScrollDown { n_rows } => {
+                let n_rows = self.scroll_distance(n_rows as u32) as usize;
+                self.for_each_selected_plane(|pixels| pixels.scroll_down(n_rows));
+            }
+            ScrollRight => {
+                let n_pixels = self.scroll_distance(4);
+                self.for_each_selected_plane(|pixels| pixels.scroll_right(n_pixels));
+            }
+            ScrollLeft => {
+                let n_pixels = self.scroll_distance(4);
+                self.for_each_selected_plane(|pixels| pixels.scroll_left(n_pixels));
+            }
+            LowResolution => {
+                self.active_pixels = Pixels::with_resolution(Resolution::Low);
+                self.second_plane = Pixels::with_resolution(Resolution::Low);
+            }
+            HighResolution => {
+                self.active_pixels = Pixels::with_resolution(Resolution::High);
+                self.second_plane = Pixels::with_resolution(Resolution::High);
+            }
+
+            SelectPlanes { planes } => self.selected_planes = planes & 0b11,
+            StoreRegisterRange {
+                register_first,
+                register_last,
+            } => {
+                for (i, register) in register_range(register_first, register_last).enumerate() {
+                    let address = self.memory_address(self.cpu.register_i as usize + i);
+                    self.write_memory(address, self.cpu.registers[register]);
+                }
+            }
+            LoadRegisterRange {
+                register_first,
+                register_last,
+            } => {
+                for (i, register) in register_range(register_first, register_last).enumerate() {
+                    let address = self.memory_address(self.cpu.register_i as usize + i);
+                    self.cpu.registers[register] = self.memory[address];
+                }
+            }
+            LoadAudioPattern => {
+                let mut pattern = [0; 16];
+                for (i, byte) in pattern.iter_mut().enumerate() {
+                    *byte = self.memory[self.memory_address(self.cpu.register_i as usize + i)];
+                }
+                self.sound_pattern = Some(pattern);
+            }
+            SetPitch { register } => self.pitch = self.cpu.registers[register],
+            CallMachineCode { address } => {
+                if let Some(machine_code_handler) = &mut self.machine_code_handler {
+                    machine_code_handler(address, &mut self.cpu);
+                }
+            }
+
+            Unknown { opcode } => {
+                let address = instruction_address;
+                match self.unknown_opcode_policy {
+                    UnknownOpcodePolicy::Ignore => {}
+                    UnknownOpcodePolicy::Log => self.log_error_at(
+                        address,
+                        &format!("unknown instruction {:#06x} at {:#06x}", opcode, address),
+                    ),
+                    UnknownOpcodePolicy::Halt => {
+                        self.halt_with_error(address, Chip8Error::UnknownOpcode(opcode))
+                    }
+                    UnknownOpcodePolicy::Panic => {
+                        panic!("unknown instruction {:#06x} at {:#06x}", opcode, address)
+                    }
+                }
+            }
+        }
+
+        return InstructionExecuteStatus::Complete;
+    }
+
+    fn plane_mut(&mut self, plane: usize) -> &mut Pixels {
+        match plane {
+            0 => &mut self.active_pixels,
+            _ => &mut self.second_plane,
+        }
+    }
+
+    fn for_each_selected_plane<F: FnMut(&mut Pixels)>(&mut self, mut f: F) {
+        for plane in 0..2 {
+            if self.selected_planes & (1 << plane) != 0 {
+                f(self.plane_mut(plane));
+            }
+        }
+    }
+
+    fn scroll_distance(&self, n_pixels: u32) -> u32 {
+        match (self.scroll_quirk, self.resolution()) {
+            (ScrollQuirk::HalvedInLowResolution, Resolution::Low) => n_pixels / 2,
+            _ => n_pixels,
+        }
+    }
+
+    fn apply_memory_increment_quirk(&mut self, last_register: usize) {
+        let increment = match self.memory_increment_quirk {
+            MemoryIncrementQuirk::Unchanged => 0,
+            MemoryIncrementQuirk::IncrementByX => last_register as u16,
+            MemoryIncrementQuirk::IncrementByXPlus1 => last_register as u16 + 1,
+        };
+        self.cpu.register_i = self.cpu.register_i.wrapping_add(increment);
+    }
+}
+
+/// Configures an `Emulator` up front; anything not set keeps the `Emulator::new`
+/// default.
+#[derive(Clone, Debug, Default)]
+pub struct EmulatorBuilder {
+    preset: Option<QuirkPreset>,
+    shift_quirk: Option<ShiftQuirk>,
+    jump_quirk: Option<JumpQuirk>,
+    memory_increment_quirk: Option<MemoryIncrementQuirk>,
+    wrap_mode: Option<WrapMode>,
+    cpu_clock: Option<CpuClock>,
+    seed: Option<u64>,
+    load_address: Option<u16>,
+    memory_size: Option<usize>,
+}
+
+impl EmulatorBuilder {
+    pub fn new() -> EmulatorBuilder {
+        EmulatorBuilder::default()
+    }
+
+    /// Applies all quirks of `preset`, see `Emulator::set_quirk_preset`. Quirks set
+    /// individually on the builder take precedence, whatever the call order.
+    pub fn preset(mut self, preset: QuirkPreset) -> EmulatorBuilder {
+        self.preset = Some(preset);
+        self
+    }
+
+    pub fn shift_quirk(mut self, shift_quirk: ShiftQuirk) -> EmulatorBuilder {
+        self.shift_quirk = Some(shift_quirk);
+        self
+    }
+
+    pub fn jump_quirk(mut self, jump_quirk: JumpQuirk) -> EmulatorBuilder {
+        self.jump_quirk = Some(jump_quirk);
+        self
+    }
+
+    pub fn memory_increment_quirk(
+        mut self,
+        memory_increment_quirk: MemoryIncrementQuirk,
+    ) -> EmulatorBuilder {
+        self.memory_increment_quirk = Some(memory_increment_quirk);
+        self
+    }
+
+    pub fn wrap_mode(mut self, wrap_mode: WrapMode) -> EmulatorBuilder {
+        self.wrap_mode = Some(wrap_mode);
+        self
+    }
+
+    pub fn cpu_clock(mut self, cpu_clock: CpuClock) -> EmulatorBuilder {
+        self.cpu_clock = Some(cpu_clock);
+        self
+    }
+
+    pub fn cpu_clock_hz(self, hz: u32) -> EmulatorBuilder {
+        self.cpu_clock(CpuClock::Hz(hz))
+    }
+
+    /// Seeds the `CXNN` random number generator, see `Emulator::with_seed`.
+    pub fn seed(mut self, seed: u64) -> EmulatorBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// `build` fails unless `load_address` is within memory.
+    pub fn load_address(mut self, load_address: u16) -> EmulatorBuilder {
+        self.load_address = Some(load_address);
+        self
+    }
+
+    /// Bytes of memory, 4KB by default or 64KB with the XO-CHIP preset. `build` fails
+    /// unless it reaches past the default load address and is at most 64KB.
+    pub fn memory_size(mut self, memory_size: usize) -> EmulatorBuilder {
+        self.memory_size = Some(memory_size);
+        self
+    }
+
+    pub fn build(self) -> Result<Emulator, Chip8Error> {
+        if let Some(memory_size) = self.memory_size {
+            if memory_size <= PROGRAM_ADDRESS || memory_size > XO_CHIP_MEMORY_SIZE {
+                return Err(Chip8Error::InvalidMemorySize(memory_size));
+            }
+        }
+        let mut emulator = match self.seed {
+            Some(seed) => Emulator::with_seed(seed),
+            None => Emulator::new(),
+        };
+        if let Some(preset) = self.preset {
+            emulator.set_quirk_preset(preset);
+        }
+        if let Some(shift_quirk) = self.shift_quirk {
+            emulator.set_shift_quirk(shift_quirk);
+        }
+        if let Some(jump_quirk) = self.jump_quirk {
+            emulator.set_jump_quirk(jump_quirk);
+        }
+        if let Some(memory_increment_quirk) = self.memory_increment_quirk {
+            emulator.set_memory_increment_quirk(memory_increment_quirk);
+        }
+        if let Some(wrap_mode) = self.wrap_mode {
+            emulator.set_wrap_mode(wrap_mode);
+        }
+        if let Some(cpu_clock) = self.cpu_clock {
+            emulator.set_cpu_clock(cpu_clock);
+        }
+        let preset_memory_size = self.preset.map(|preset| {
+            if preset.uses_wide_memory() {
+                XO_CHIP_MEMORY_SIZE
+            } else {
+                MEMORY_SIZE
+            }
+        });
+        if let Some(memory_size) = self.memory_size.or(preset_memory_size) {
+            emulator.set_memory_size(memory_size);
+        }
+        if let Some(load_address) = self.load_address {
+            emulator.set_load_address(load_address)?;
+        }
+        Ok(emulator)
+    }
+}
+
+/// Registers from `first` to `last` inclusive, counting down when `first > last`.
+fn register_range(first: usize, last: usize) -> impl Iterator<Item = usize> {
+    (0..=first.abs_diff(last)).map(move |i| if first <= last { first + i } else { first - i })
+}
+
+/// Formats an instruction histogram as one `count name` line per instruction,
+/// most frequent first.
+pub fn format_instruction_histogram(histogram: &HashMap<&'static str, u64>) -> String {
+    let mut entries: Vec<(&&'static str, &u64)> = histogram.iter().collect();
+    entries.sort_by(|lhs, rhs| rhs.1.cmp(lhs.1).then(lhs.0.cmp(rhs.0)));
+
+    let mut output = String::new();
+    for (name, count) in entries {
+        output.push_str(&format!("{:>10}  {}\n", count, name));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_hex::assert_eq_hex;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_convert_from_instruction_to_opcode() {
+        use Instruction::*;
+        assert_eq_hex!(ClearDisplay.to_opcode(), 0x00E0);
+        assert_eq_hex!(Return.to_opcode(), 0x00EE);
+        assert_eq_hex!(Jump{address: 0x04F1}.to_opcode(), 0x14F1);
+        assert_eq_hex!(Call{address: 0x07AB}.to_opcode(), 0x27AB);
+        assert_eq_hex!(SkipIfRegEqConstant{register: 0xA, constant: 0xC3}.to_opcode(), 0x3AC3);
+        assert_eq_hex!(SkipIfRegNotEqConstant{register: 1, constant: 0x23}.to_opcode(), 0x4123);
+        assert_eq_hex!(SkipIfRegEqReg{register_lhs: 0xA, register_rhs: 0xD}.to_opcode(), 0x5AD0);
+        assert_eq_hex!(SetRegToConstant{register: 7, constant: 0xAF}.to_opcode(), 0x67AF);
+        assert_eq_hex!(AddConstToReg{register: 0xC, constant: 0x42}.to_opcode(), 0x7C42);
+        assert_eq_hex!(SetRegToReg{register_lhs: 0x9, register_rhs: 0x3}.to_opcode(), 0x8930);
+        assert_eq_hex!(BitwiseOr{register_lhs: 0x5, register_rhs: 0xF}.to_opcode(), 0x85F1);
+        assert_eq_hex!(BitwiseAnd{register_lhs: 0x5, register_rhs: 0xF}.to_opcode(), 0x85F2);
+        assert_eq_hex!(BitwiseXor{register_lhs: 0x5, register_rhs: 0xF}.to_opcode(), 0x85F3);
+        assert_eq_hex!(AddRegToReg{register_lhs: 0x6, register_rhs: 0x0}.to_opcode(), 0x8604);
+        assert_eq_hex!(SubReg2FromReg1{register_lhs: 0xA, register_rhs: 0xB}.to_opcode(), 0x8AB5);
+        assert_eq_hex!(BitwiseShrBy1{register_lhs: 0x9, register_rhs: 0x3}.to_opcode(), 0x8936);
+        assert_eq_hex!(SubReg1FromReg2{register_lhs: 0xA, register_rhs: 0xB}.to_opcode(), 0x8AB7);
+        assert_eq_hex!(BitwiseShlBy1{register_lhs: 0x9, register_rhs: 0x3}.to_opcode(), 0x893E);
+        assert_eq_hex!(CondRegNotEqReg{register_lhs: 0xA, register_rhs: 0xB}.to_opcode(), 0x9AB0);
+        assert_eq_hex!(SetAddress{address: 0x123}.to_opcode(), 0xA123);
+        assert_eq_hex!(JumpWithV0Offset{address: 0x123}.to_opcode(), 0xB123);
         assert_eq_hex!(BitwiseAndWithRand{register: 0xA, constant: 0xB4}.to_opcode(), 0xCAB4);
         assert_eq_hex!(DisplaySprite{register_x: 0xA, register_y: 0xB, n_bytes: 9}.to_opcode(), 0xDAB9);
         assert_eq_hex!(SkipIfKeyPressed{register: 0x5}.to_opcode(), 0xE59E);
@@ -789,891 +2909,4470 @@ mod tests {
         assert_eq_hex!(SetSoundTimer{register: 0x3}.to_opcode(), 0xF318);
         assert_eq_hex!(AddRegToAddressWithoutCarry{register: 0x5}.to_opcode(), 0xF51E);
         assert_eq_hex!(SetAddressOfFontChar{register: 0x5}.to_opcode(), 0xF529);
+        assert_eq_hex!(SetAddressOfBigFontChar{register: 0x5}.to_opcode(), 0xF530);
+        assert_eq_hex!(SetLongAddress.to_opcode(), 0xF000);
         assert_eq_hex!(StoreRegBcd{register: 0x7}.to_opcode(), 0xF733);
         assert_eq_hex!(StoreRegisters{last_register: 0x7}.to_opcode(), 0xF755);
         assert_eq_hex!(LoadRegisters{last_register: 0x7}.to_opcode(), 0xF765);
+        assert_eq_hex!(ScrollDown{n_rows: 0x5}.to_opcode(), 0x00C5);
+        assert_eq_hex!(ScrollRight.to_opcode(), 0x00FB);
+        assert_eq_hex!(ScrollLeft.to_opcode(), 0x00FC);
+        assert_eq_hex!(LowResolution.to_opcode(), 0x00FE);
+        assert_eq_hex!(HighResolution.to_opcode(), 0x00FF);
+        assert_eq_hex!(SelectPlanes{planes: 0x3}.to_opcode(), 0xF301);
+        assert_eq_hex!(StoreRegisterRange{register_first: 0x2, register_last: 0x9}.to_opcode(), 0x5292);
+        assert_eq_hex!(LoadRegisterRange{register_first: 0x9, register_last: 0x2}.to_opcode(), 0x5923);
+        assert_eq_hex!(LoadAudioPattern.to_opcode(), 0xF002);
+        assert_eq_hex!(SetPitch{register: 0x4}.to_opcode(), 0xF43A);
+        assert_eq_hex!(CallMachineCode{address: 0x123}.to_opcode(), 0x0123);
+    }
+
+    #[test]
+    fn should_convert_between_opcode_and_instruction() {
+        use Instruction::*;
+
+        for opcode in [
+            0x00E0, 0x14F1, 0x3AC3, 0x8AB5, 0xDAB9, 0xF733, 0x5292, 0x5121,
+        ] {
+            let instruction: Instruction = opcode.into();
+            assert_eq_hex!(u16::from(&instruction), opcode);
+            assert_eq_hex!(u16::from(instruction), opcode);
+        }
+        assert_eq!(
+            Instruction::from(0x3AC3),
+            SkipIfRegEqConstant {
+                register: 0xA,
+                constant: 0xC3
+            }
+        );
+        assert_eq!(Instruction::from(0x5121), Unknown { opcode: 0x5121 });
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_format_instruction_as_mnemonic() {
+        use Instruction::*;
+        assert_eq!(ClearDisplay.to_string(), "CLS");
+        assert_eq!(Jump{address: 0x4F1}.to_string(), "JP 0x4F1");
+        assert_eq!(SkipIfRegEqConstant{register: 3, constant: 0x7D}.to_string(), "SE V3, 0x7D");
+        assert_eq!(AddRegToReg{register_lhs: 0xA, register_rhs: 0x1}.to_string(), "ADD VA, V1");
+        assert_eq!(SetAddress{address: 0x02A}.to_string(), "LD I, 0x02A");
+        assert_eq!(DisplaySprite{register_x: 0xA, register_y: 0xB, n_bytes: 9}.to_string(), "DRW VA, VB, 0x9");
+        assert_eq!(StoreRegBcd{register: 0x7}.to_string(), "LD B, V7");
+        assert_eq!(Unknown{opcode: 0x5121}.to_string(), "DW 0x5121");
+        assert_eq!(
+            format!("{:?}", SkipIfRegEqConstant{register: 3, constant: 0x7D}),
+            "SkipIfRegEqConstant { register: 3, constant: 125 }"
+        );
+    }
+
+    #[test]
+    fn should_decode_call_machine_code() {
+        use Instruction::*;
+
+        assert_eq!(
+            Instruction::decode(0x0123),
+            CallMachineCode { address: 0x123 }
+        );
+        assert_eq!(Instruction::decode(0x00E0), ClearDisplay);
+        assert_eq!(Instruction::decode(0x0000), Unknown { opcode: 0x0000 });
+        assert_eq!(CallMachineCode { address: 0x123 }.to_string(), "SYS 0x123");
+    }
+
+    #[test]
+    fn should_ignore_call_machine_code_without_handler() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        let cpu = emulator.cpu_snapshot();
+
+        // When
+        emulator.execute(CallMachineCode { address: 0x123 });
+
+        // Then
+        assert_eq_hex!(emulator.program_counter(), cpu.program_counter + 2);
+        assert_eq!(emulator.cpu.registers, cpu.registers);
+        assert!(!emulator.is_halted());
+    }
+
+    #[test]
+    fn should_call_machine_code_handler() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_machine_code_handler(Box::new(|address, cpu| {
+            cpu.registers[0x0] = (address & 0xFF) as u8;
+        }));
+
+        // When
+        emulator.execute(CallMachineCode { address: 0x123 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x23);
+    }
+
+    #[test]
+    fn should_execute_clear_display() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.active_pixels.extend([(1, 1), (10, 15), (21, 30)]);
+
+        // When
+        emulator.execute(ClearDisplay);
+
+        // Then
+        assert_eq!(emulator.active_pixels.len(), 0);
+    }
+
+    #[test]
+    fn should_execute_jump() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+
+        // When
+        emulator.execute(Jump { address: 0x123 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.program_counter, 0x123);
+    }
+
+    #[test]
+    fn should_halt_on_jump_to_itself() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[0x12, 0x00]).unwrap();
+
+        // When
+        emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert!(emulator.is_halted());
+        assert!(emulator.last_error().is_none());
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+    }
+
+    #[test]
+    fn should_not_halt_on_jump_elsewhere() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program_from_data(&[0x12, 0x02, 0x12, 0x00])
+            .unwrap();
+
+        // When
+        emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert!(!emulator.is_halted());
+        assert_eq_hex!(emulator.program_counter(), 0x202);
+    }
+
+    #[test]
+    fn should_execute_call() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        let pc = emulator.cpu.program_counter;
+
+        // When
+        emulator.execute(Call { address: 0x123 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.program_counter, 0x123);
+        assert_eq!(emulator.cpu.stack_index, 0);
+        assert_eq!(
+            emulator.cpu.stack[emulator.cpu.stack_index as usize],
+            pc + 2
+        );
+    }
+
+    #[test]
+    fn should_execute_return() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.stack[0] = 0x123;
+        emulator.cpu.stack_index = 0;
+
+        // When
+        emulator.execute(Return);
+
+        // Then
+        assert_eq_hex!(emulator.cpu.program_counter, 0x123);
+        assert_eq!(emulator.cpu.stack_index, -1);
+    }
+
+    #[test]
+    fn should_execute_skip_if_req_eq_constant() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x7d;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfRegEqConstant {
+                register: 0x3,
+                constant: 0x7d,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 4);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x6c;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfRegEqConstant {
+                register: 0x3,
+                constant: 0x7d,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 2);
+        }
+    }
+
+    #[test]
+    fn should_execute_skip_if_req_not_eq_constant() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x7d;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfRegNotEqConstant {
+                register: 0x3,
+                constant: 0x7d,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 2);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x6c;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfRegNotEqConstant {
+                register: 0x3,
+                constant: 0x7d,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 4);
+        }
+    }
+
+    #[test]
+    fn should_execute_skip_if_req_eq_req() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0x5] = 0x42;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfRegEqReg {
+                register_lhs: 0x3,
+                register_rhs: 0x5,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 4);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0x5] = 0x71;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfRegEqReg {
+                register_lhs: 0x3,
+                register_rhs: 0x5,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 2);
+        }
+    }
+
+    #[test]
+    fn should_find_any_key_pressed() {
+        // Given
+        let mut emulator = Emulator::new();
+        assert_eq!(emulator.any_key_pressed(), None);
+
+        // When
+        emulator.press_key(0xC);
+        emulator.press_key(0x4);
+
+        // Then
+        assert_eq!(emulator.any_key_pressed(), Some(0x4));
+    }
+
+    #[test]
+    fn should_detect_key_edges_between_steps() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_keypad(0b0011);
+        emulator.step(Duration::ZERO);
+
+        // When
+        emulator.set_keypad(0b0110);
+
+        // Then
+        assert_eq!(emulator.newly_pressed(), vec![2]);
+        assert_eq!(emulator.newly_released(), vec![0]);
+
+        // When
+        emulator.step(Duration::ZERO);
+
+        // Then
+        assert!(emulator.newly_pressed().is_empty());
+        assert!(emulator.newly_released().is_empty());
+    }
+
+    #[test]
+    fn should_press_and_release_keys() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0xA;
+
+        // When
+        emulator.press_key(0xA);
+        let pc = emulator.cpu.program_counter;
+        emulator.execute(SkipIfKeyPressed { register: 0x3 });
+
+        // Then
+        assert!(emulator.is_key_down(0xA));
+        assert_eq!(emulator.keypad(), 1 << 0xA);
+        assert_eq!(emulator.cpu.program_counter, pc + 4);
+
+        // When
+        emulator.release_key(0xA);
+        let pc = emulator.cpu.program_counter;
+        emulator.execute(SkipIfKeyPressed { register: 0x3 });
+
+        // Then
+        assert!(!emulator.is_key_down(0xA));
+        assert_eq!(emulator.cpu.program_counter, pc + 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "key 0x10 is not on the hex keypad")]
+    fn should_reject_key_outside_keypad() {
+        let mut emulator = Emulator::new();
+        emulator.press_key(0x10);
+    }
+
+    #[test]
+    fn should_check_low_nibble_of_out_of_range_key() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.press_key(0xF);
+        emulator.cpu.registers[0x3] = 0xFF;
+        emulator.cpu.registers[0x4] = 0x10;
+        let pc = emulator.cpu.program_counter;
+
+        // When
+        emulator.execute(SkipIfKeyPressed { register: 0x3 });
+        emulator.execute(SkipIfKeyNotPressed { register: 0x4 });
+
+        // Then
+        assert_eq!(emulator.cpu.program_counter, pc + 8);
+    }
+
+    #[test]
+    fn should_execute_skip_if_key_pressed() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.input[0xA] = true;
+            emulator.cpu.registers[0x3] = 0xA;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfKeyPressed { register: 0x3 });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 4);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.input[0xA] = false;
+            emulator.cpu.registers[0x3] = 0xA;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfKeyPressed { register: 0x3 });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 2);
+        }
+    }
+
+    #[test]
+    fn should_execute_skip_if_not_key_pressed() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.input[0xA] = true;
+            emulator.cpu.registers[0x3] = 0xA;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfKeyNotPressed { register: 0x3 });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 2);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.input[0xA] = false;
+            emulator.cpu.registers[0x3] = 0xA;
+            let pc = emulator.cpu.program_counter;
+
+            // When
+            emulator.execute(SkipIfKeyNotPressed { register: 0x3 });
+
+            // Then
+            assert_eq!(emulator.cpu.program_counter, pc + 4);
+        }
+    }
+
+    #[test]
+    fn should_execute_set_reg_to_constant() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x4] = 0x42;
+
+        // When
+        emulator.execute(SetRegToConstant {
+            register: 0x4,
+            constant: 0xD7,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x4], 0xD7);
+    }
+
+    #[test]
+    fn should_execute_add_const_to_reg() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x4] = 0x27;
+
+            // When
+            emulator.execute(AddConstToReg {
+                register: 0x4,
+                constant: 0xD7,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x4], 0x27 + 0xD7);
+            assert_eq_hex!(emulator.cpu.registers[0xF], 0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x4] = 0xff;
+
+            // When
+            emulator.execute(AddConstToReg {
+                register: 0x4,
+                constant: 0x01,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x4], 0x0);
+            assert_eq_hex!(emulator.cpu.registers[0xF], 0);
+        }
+    }
+
+    #[test]
+    fn should_execute_set_reg_to_reg() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0x42;
+        emulator.cpu.registers[0xa] = 0xd5;
+
+        // When
+        emulator.execute(SetRegToReg {
+            register_lhs: 0x3,
+            register_rhs: 0xa,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0xd5);
+    }
+
+    #[test]
+    fn should_execute_bitwise_or() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0x42;
+        emulator.cpu.registers[0xa] = 0xd5;
+
+        // When
+        emulator.execute(BitwiseOr {
+            register_lhs: 0x3,
+            register_rhs: 0xa,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 | 0xd5);
+    }
+
+    #[test]
+    fn should_execute_bitwise_and() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0x42;
+        emulator.cpu.registers[0xa] = 0xd5;
+
+        // When
+        emulator.execute(BitwiseAnd {
+            register_lhs: 0x3,
+            register_rhs: 0xa,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 & 0xd5);
+    }
+
+    #[test]
+    fn should_execute_bitwise_xor() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0x42;
+        emulator.cpu.registers[0xa] = 0xd5;
+
+        // When
+        emulator.execute(BitwiseXor {
+            register_lhs: 0x3,
+            register_rhs: 0xa,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 ^ 0xd5);
+    }
+
+    #[test]
+    fn should_reset_vf_after_logic_ops_with_logic_vf_reset_quirk() {
+        use Instruction::*;
+
+        #[rustfmt::skip]
+        let cases = [
+            (BitwiseOr { register_lhs: 0x3, register_rhs: 0xa }, 0x42 | 0xd5),
+            (BitwiseAnd { register_lhs: 0x3, register_rhs: 0xa }, 0x42 & 0xd5),
+            (BitwiseXor { register_lhs: 0x3, register_rhs: 0xa }, 0x42 ^ 0xd5),
+        ];
+        for (instruction, result) in cases {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.set_logic_vf_reset_quirk(true);
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0xa] = 0xd5;
+            emulator.cpu.registers[0xF] = 0x7;
+
+            // When
+            emulator.execute(instruction);
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], result);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+    }
+
+    #[test]
+    fn should_keep_vf_after_logic_ops_without_logic_vf_reset_quirk() {
+        use Instruction::*;
+
+        #[rustfmt::skip]
+        let cases = [
+            (BitwiseOr { register_lhs: 0x3, register_rhs: 0xa }, 0x42 | 0xd5),
+            (BitwiseAnd { register_lhs: 0x3, register_rhs: 0xa }, 0x42 & 0xd5),
+            (BitwiseXor { register_lhs: 0x3, register_rhs: 0xa }, 0x42 ^ 0xd5),
+        ];
+        for (instruction, result) in cases {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0xa] = 0xd5;
+            emulator.cpu.registers[0xF] = 0x7;
+
+            // When
+            emulator.execute(instruction);
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], result);
+            assert_eq!(emulator.cpu.registers[0xF], 0x7);
+        }
+    }
+
+    #[test]
+    fn should_execute_add_reg_to_reg() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0xa] = 0x65;
+
+            // When
+            emulator.execute(AddRegToReg {
+                register_lhs: 0x3,
+                register_rhs: 0xa,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 + 0x65);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0xff;
+            emulator.cpu.registers[0xa] = 0x1;
+
+            // When
+            emulator.execute(AddRegToReg {
+                register_lhs: 0x3,
+                register_rhs: 0xa,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0x0);
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+    }
+
+    #[test]
+    fn should_execute_sub_reg2_from_reg1() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x65;
+            emulator.cpu.registers[0x4] = 0x42;
+
+            // When
+            emulator.execute(SubReg2FromReg1 {
+                register_lhs: 0x3,
+                register_rhs: 0x4,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0x65 - 0x42);
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x0;
+            emulator.cpu.registers[0x4] = 0x4;
+
+            // When
+            emulator.execute(SubReg2FromReg1 {
+                register_lhs: 0x3,
+                register_rhs: 0x4,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0xfc);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0x4] = 0x42;
+
+            // When
+            emulator.execute(SubReg2FromReg1 {
+                register_lhs: 0x3,
+                register_rhs: 0x4,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0x0);
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+    }
+
+    #[test]
+    fn should_execute_sub_reg1_from_reg2() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0x4] = 0x65;
+
+            // When
+            emulator.execute(SubReg1FromReg2 {
+                register_lhs: 0x3,
+                register_rhs: 0x4,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0x65 - 0x42);
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x7;
+            emulator.cpu.registers[0x4] = 0x0;
+
+            // When
+            emulator.execute(SubReg1FromReg2 {
+                register_lhs: 0x3,
+                register_rhs: 0x4,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0xf9);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0x42;
+            emulator.cpu.registers[0x4] = 0x42;
+
+            // When
+            emulator.execute(SubReg1FromReg2 {
+                register_lhs: 0x3,
+                register_rhs: 0x4,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0x0);
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+    }
+
+    #[test]
+    fn should_execute_bitwise_shr_by_1() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0b11001101;
+
+            // When
+            emulator.execute(BitwiseShrBy1 {
+                register_lhs: 0x3,
+                register_rhs: 0x5,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0b1100110);
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0b10001110;
+
+            // When
+            emulator.execute(BitwiseShrBy1 {
+                register_lhs: 0x3,
+                register_rhs: 0x5,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0b1000111);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+    }
+
+    #[test]
+    fn should_execute_bitwise_shl_by_1() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0b1101;
+
+            // When
+            emulator.execute(BitwiseShlBy1 {
+                register_lhs: 0x3,
+                register_rhs: 0x5,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0b11010);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x3] = 0b11001110;
+
+            // When
+            emulator.execute(BitwiseShlBy1 {
+                register_lhs: 0x3,
+                register_rhs: 0x5,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.registers[0x3], 0b10011100);
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+    }
+
+    #[test]
+    fn should_execute_set_address() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.register_i = 0x0;
+
+        // When
+        emulator.execute(SetAddress { address: 0x456 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.register_i, 0x456);
+    }
+
+    #[test]
+    fn should_execute_jump_with_v0_offset() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0] = 0xff;
+
+        // When
+        emulator.execute(JumpWithV0Offset { address: 0x456 });
+
+        // Then
+        assert_eq_hex!(
+            emulator.cpu.program_counter,
+            0x456 + emulator.cpu.registers[0] as u16
+        );
+    }
+
+    #[test]
+    fn should_execute_display_sprite_no_xor() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[2] = 20;
+        emulator.cpu.registers[3] = 10;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0b10101010;
+        emulator.memory[0x601] = 0b00111010;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 2,
+        });
+
+        // Then
+        assert_eq!(emulator.active_pixels.len(), 8);
+        assert!(emulator.active_pixels.contains(&(20, 10)));
+        assert!(emulator.active_pixels.contains(&(22, 10)));
+        assert!(emulator.active_pixels.contains(&(24, 10)));
+        assert!(emulator.active_pixels.contains(&(26, 10)));
+        assert!(emulator.active_pixels.contains(&(22, 11)));
+        assert!(emulator.active_pixels.contains(&(23, 11)));
+        assert!(emulator.active_pixels.contains(&(24, 11)));
+        assert!(emulator.active_pixels.contains(&(26, 11)));
+        assert_eq!(emulator.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn should_execute_display_sprite_xor() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[2] = 20;
+        emulator.cpu.registers[3] = 10;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0b10101010;
+        emulator.active_pixels.insert((22, 10));
+        emulator.active_pixels.insert((26, 10));
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 1,
+        });
+
+        // Then
+        assert_eq!(emulator.active_pixels.len(), 2);
+        assert!(emulator.active_pixels.contains(&(20, 10)));
+        assert!(emulator.active_pixels.contains(&(24, 10)));
+        assert_eq!(emulator.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn should_execute_display_sprite_near_edge() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[2] = (SCREEN_WIDTH - 3) as u8;
+        emulator.cpu.registers[3] = (SCREEN_HEIGHT - 1) as u8;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0b10101010;
+        emulator.memory[0x601] = 0b01101011;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 2,
+        });
+
+        // Then
+        assert_eq!(emulator.active_pixels.len(), 2);
+        assert!(emulator
+            .active_pixels
+            .contains(&(SCREEN_WIDTH - 3, SCREEN_HEIGHT - 1)));
+        assert!(emulator
+            .active_pixels
+            .contains(&(SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1)));
+        assert_eq!(emulator.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn should_execute_display_sprite_wrap() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[2] = (7 * SCREEN_WIDTH + 5) as u8;
+        emulator.cpu.registers[3] = (2 * SCREEN_HEIGHT + 10) as u8;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0b10000010;
+        emulator.memory[0x601] = 0b01001001;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 2,
+        });
+
+        // Then
+        assert_eq!(emulator.active_pixels.len(), 5);
+        assert!(emulator.active_pixels.contains(&(5, 10)));
+        assert!(emulator.active_pixels.contains(&(11, 10)));
+        assert!(emulator.active_pixels.contains(&(6, 11)));
+        assert!(emulator.active_pixels.contains(&(9, 11)));
+        assert!(emulator.active_pixels.contains(&(12, 11)));
+        assert_eq!(emulator.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn should_execute_set_reg_to_delay_timer() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.delay_timer = 42;
+
+        // When
+        emulator.execute(SetRegToDelayTimer { register: 0x3 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 42);
+    }
+
+    #[test]
+    fn should_execute_await_and_set_key_press() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.input[0xC] = true;
+
+        // When
+        emulator.execute(AwaitAndSetKeyPress { register: 0x3 });
+        emulator.input[0xC] = false;
+        emulator.execute(AwaitAndSetKeyPress { register: 0x3 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0xC);
+    }
+
+    #[test]
+    fn should_execute_await_and_set_key_press_with_no_delay() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+
+        // When
+        emulator.input[0xC] = false;
+        emulator.load_instructions(vec![AwaitAndSetKeyPress { register: 0x3 }]);
+        emulator.step(Duration::from_nanos(1));
+        emulator.input[0xC] = true;
+        emulator.step(Duration::from_nanos(1));
+        emulator.input[0xC] = false;
+        emulator.step(Duration::from_nanos(1));
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0xC);
+    }
+
+    #[test]
+    fn should_not_complete_await_key_press_until_key_released() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![AwaitAndSetKeyPress { register: 0x3 }]);
+        emulator.input[0x7] = true;
+
+        // When
+        for _ in 0..5 {
+            emulator.step_instruction();
+        }
+
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0x0);
+
+        // When
+        emulator.input[0x7] = false;
+        emulator.step_instruction();
+
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x202);
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0x7);
+    }
+
+    #[test]
+    fn should_execute_await_and_set_delay_timer() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0x7d;
+
+        // When
+        emulator.execute(SetDelayTimer { register: 0x3 });
+
+        // Then
+        assert_eq!(emulator.cpu.delay_timer, 0x7d);
+    }
+
+    #[test]
+    fn should_execute_await_and_set_sound_timer() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0x7d;
+
+        // When
+        emulator.execute(SetSoundTimer { register: 0x3 });
+
+        // Then
+        assert_eq!(emulator.cpu.sound_timer, 0x7d);
+    }
+
+    #[test]
+    fn should_execute_add_reg_to_address_without_carry() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.register_i = 0xd79;
+            emulator.cpu.registers[0x3] = 0x7d;
+
+            // When
+            emulator.execute(AddRegToAddressWithoutCarry { register: 0x3 });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.register_i, 0xd79 + 0x7d);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.register_i = 0xf79;
+            emulator.cpu.registers[0x3] = 0x7d;
+
+            // When
+            emulator.execute(AddRegToAddressWithoutCarry { register: 0x3 });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.register_i, 0xf79 + 0x7d);
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+    }
+
+    #[test]
+    fn should_set_carry_on_address_overflow_with_address_carry_quirk() {
+        use Instruction::*;
+
+        for (register_i, expected_carry) in [(0xf79, 0), (0xfa9, 1)] {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.set_address_carry_quirk(true);
+            emulator.cpu.register_i = register_i;
+            emulator.cpu.registers[0x3] = 0x7d;
+            emulator.cpu.registers[0xF] = 0xAA;
+
+            // When
+            emulator.execute(AddRegToAddressWithoutCarry { register: 0x3 });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.register_i, register_i + 0x7d);
+            assert_eq!(emulator.cpu.registers[0xF], expected_carry);
+        }
+    }
+
+    #[test]
+    fn should_execute_store_reg_bcd() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 196;
+        emulator.cpu.register_i = 0x765;
+
+        // When
+        emulator.execute(StoreRegBcd { register: 0x3 });
+
+        // Then
+        assert_eq!(emulator.memory[emulator.cpu.register_i as usize + 0], 1);
+        assert_eq!(emulator.memory[emulator.cpu.register_i as usize + 1], 9);
+        assert_eq!(emulator.memory[emulator.cpu.register_i as usize + 2], 6);
+    }
+
+    #[test]
+    fn should_halt_on_store_reg_bcd_past_end_of_memory() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_log_level(LogLevel::Off);
+        emulator.load_program_from_data(&[0xF3, 0x33]).unwrap();
+        emulator.cpu.registers[0x3] = 196;
+        emulator.cpu.register_i = 0xFFE;
+        let font = emulator.memory[0x000];
+
+        // When
+        emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert!(emulator.is_halted());
+        assert!(matches!(
+            emulator.last_error(),
+            Some(Chip8Error::AddressOutOfBounds(0xFFE))
+        ));
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+        assert_eq!(emulator.memory[0xFFE], 0);
+        assert_eq!(emulator.memory[0xFFF], 0);
+        assert_eq_hex!(emulator.memory[0x000], font);
+    }
+
+    #[test]
+    fn should_execute_store_registers() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x0] = 0x41;
+        emulator.cpu.registers[0x1] = 0xb7;
+        emulator.cpu.registers[0x2] = 0x09;
+        emulator.cpu.registers[0x3] = 0xff;
+        emulator.cpu.register_i = 0x765;
+
+        // When
+        emulator.execute(StoreRegisters { last_register: 0x2 });
+
+        // Then
+        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 0], 0x41);
+        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 1], 0xb7);
+        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 2], 0x09);
+        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 3], 0);
+    }
+
+    #[test]
+    fn should_execute_load_registers() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x0] = 0xff;
+        emulator.cpu.registers[0x1] = 0xff;
+        emulator.cpu.registers[0x2] = 0xff;
+        emulator.cpu.registers[0x3] = 0xff;
+        emulator.cpu.register_i = 0x765;
+        emulator.memory[emulator.cpu.register_i as usize + 0] = 0x71;
+        emulator.memory[emulator.cpu.register_i as usize + 1] = 0xa5;
+        emulator.memory[emulator.cpu.register_i as usize + 2] = 0x06;
+        emulator.memory[emulator.cpu.register_i as usize + 3] = 0x51;
+
+        // When
+        emulator.execute(LoadRegisters { last_register: 0x2 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x71);
+        assert_eq_hex!(emulator.cpu.registers[0x1], 0xa5);
+        assert_eq_hex!(emulator.cpu.registers[0x2], 0x06);
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0xff);
+    }
+
+    #[test]
+    fn should_count_executed_instructions_when_profiling() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_profiling(true);
+
+        // When
+        emulator.execute(SetAddress { address: 0x456 });
+        emulator.execute(SetAddress { address: 0x123 });
+        emulator.execute(ClearDisplay);
+
+        // Then
+        assert_eq!(emulator.instruction_histogram().len(), 2);
+        assert_eq!(emulator.instruction_histogram()["SetAddress"], 2);
+        assert_eq!(emulator.instruction_histogram()["ClearDisplay"], 1);
+    }
+
+    #[test]
+    fn should_format_instruction_histogram_sorted_by_count() {
+        // Given
+        let histogram = HashMap::from([
+            ("Jump", 3),
+            ("DisplaySprite", 42),
+            ("ClearDisplay", 1),
+            ("Call", 3),
+        ]);
+
+        // When
+        let output = format_instruction_histogram(&histogram);
+
+        // Then
+        assert_eq!(
+            output,
+            "        42  DisplaySprite\n\
+             \x20        3  Call\n\
+             \x20        3  Jump\n\
+             \x20        1  ClearDisplay\n"
+        );
+    }
+
+    #[test]
+    fn should_cycle_through_all_quirk_presets_and_wrap() {
+        // Given
+        let mut preset = QuirkPreset::CosmacVip;
+        let mut visited = vec![preset];
+
+        // When
+        for _ in 1..QuirkPreset::ALL.len() {
+            preset = preset.next();
+            visited.push(preset);
+        }
+
+        // Then
+        assert_eq!(visited, QuirkPreset::ALL);
+        assert_eq!(preset.next(), QuirkPreset::CosmacVip);
+    }
+
+    #[test]
+    fn should_set_quirk_preset() {
+        // Given
+        let mut emulator = Emulator::new();
+        assert_eq!(emulator.quirk_preset(), None);
+
+        // When
+        emulator.set_quirk_preset(QuirkPreset::SuperChip);
+
+        // Then
+        assert_eq!(emulator.quirk_preset(), Some(QuirkPreset::SuperChip));
+        assert_eq!(emulator.shift_quirk, ShiftQuirk::InPlace);
+        assert!(!emulator.logic_vf_reset_quirk);
+
+        // When
+        emulator.set_quirk_preset(QuirkPreset::CosmacVip);
+
+        // Then
+        assert_eq!(emulator.shift_quirk, ShiftQuirk::CopyFromVy);
+        assert!(emulator.logic_vf_reset_quirk);
+    }
+
+    #[test]
+    fn should_set_pc_masked_to_12_bits() {
+        // Given
+        let mut emulator = Emulator::new();
+
+        // When
+        let result = emulator.set_pc(0x1234);
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq_hex!(emulator.program_counter(), 0x234);
+    }
+
+    #[test]
+    fn should_not_set_pc_past_last_instruction() {
+        // Given
+        let mut emulator = Emulator::new();
+
+        // When
+        let result = emulator.set_pc(0xFFF);
+
+        // Then
+        assert!(matches!(result, Err(Chip8Error::AddressOutOfBounds(0xFFF))));
+        assert_eq_hex!(emulator.program_counter(), 512);
+    }
+
+    #[test]
+    fn should_wait_for_vblank_after_display_sprite_with_display_wait_quirk() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_display_wait_quirk(true);
+        emulator.load_instructions(vec![
+            DisplaySprite {
+                register_x: 0,
+                register_y: 0,
+                n_bytes: 5,
+            },
+            DisplaySprite {
+                register_x: 0,
+                register_y: 0,
+                n_bytes: 5,
+            },
+        ]);
+
+        // When
+        emulator.step(Duration::from_nanos(1));
+
+        // Then
+        assert!(emulator.is_waiting_for_vblank());
+        assert_eq_hex!(emulator.program_counter(), 0x202);
+
+        // When
+        for _ in 0..8 {
+            emulator.step(Duration::from_millis(2));
+        }
+
+        // Then
+        assert!(emulator.is_waiting_for_vblank());
+        assert_eq_hex!(emulator.program_counter(), 0x202);
+
+        // When
+        emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x204);
+        assert!(emulator.is_waiting_for_vblank());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_draw_once_per_frame_with_display_wait_quirk() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_display_wait_quirk(true);
+        emulator.set_cpu_clock(CpuClock::Hz(100_000));
+        emulator.set_profiling(true);
+        emulator.load_program_from_data(&[
+            0xD0, 0x05, // DRW V0, V0, 5
+            0x12, 0x00, // JP 0x200
+        ]).unwrap();
+
+        // When
+        for _ in 0..3 {
+            emulator.step(TIMER_INTERVAL);
+        }
+
+        // Then
+        assert_eq!(emulator.instruction_histogram()["DisplaySprite"], 3);
+        assert_eq!(emulator.instruction_histogram()["Jump"], 2);
+    }
+
+    #[test]
+    fn should_not_wait_for_vblank_without_display_wait_quirk() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 0,
+            register_y: 0,
+            n_bytes: 5,
+        });
+
+        // Then
+        assert!(!emulator.is_waiting_for_vblank());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_format_disassembly_context_around_address() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[
+            0x00, 0xE0,
+            0x6A, 0x05,
+            0xA2, 0x2A,
+            0xFF, 0xFF,
+            0xDA, 0xB5,
+            0x12, 0x00,
+        ]).unwrap();
+
+        // When
+        let lines = emulator.disassembly_context(0x206, 2);
+
+        // Then
+        assert_eq!(lines, vec![
+            "  0x0202: 6A05  LD VA, 0x05",
+            "  0x0204: A22A  LD I, 0x22A",
+            "> 0x0206: FFFF  DW 0xFFFF",
+            "  0x0208: DAB5  DRW VA, VB, 0x5",
+            "  0x020a: 1200  JP 0x200",
+        ]);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_format_long_address_in_disassembly_context_as_data() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[
+            0xF0, 0x00,
+            0x12, 0x34,
+        ]).unwrap();
+
+        // When
+        let lines = emulator.disassembly_context(0x200, 1);
+
+        // Then
+        assert_eq!(lines, vec![
+            "  0x01fe: 0000  DW 0x0000",
+            "> 0x0200: F000  LD I, LONG",
+            "  0x0202: 1234  DW 0x1234",
+        ]);
+    }
+
+    #[test]
+    fn should_stop_disassembly_context_at_end_of_memory() {
+        // Given
+        let emulator = Emulator::new();
+
+        // When
+        let lines = emulator.disassembly_context(0xFFC, 2);
+
+        // Then
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].starts_with("> 0x0ffc"));
+        assert_eq!(emulator.opcode_at(0xFFF), None);
+    }
+
+    #[test]
+    fn should_draw_font_char() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x0] = 0x0;
+        emulator.cpu.registers[0x1] = 10;
+        emulator.cpu.registers[0x2] = 3;
+
+        // When
+        emulator.execute(SetAddressOfFontChar { register: 0x0 });
+        emulator.execute(DisplaySprite {
+            register_x: 0x1,
+            register_y: 0x2,
+            n_bytes: 5,
+        });
+
+        // Then
+        let mut expected = Pixels::new();
+        for x in 10..14 {
+            expected.insert((x, 3));
+            expected.insert((x, 7));
+        }
+        for y in 4..7 {
+            expected.insert((10, y));
+            expected.insert((13, y));
+        }
+        assert_eq!(emulator.active_pixels, expected);
+    }
+
+    #[test]
+    fn should_set_keypad_from_bitmask() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.input[0x3] = true;
+
+        // When
+        emulator.set_keypad(0b1000_0000_0010_0001);
+
+        // Then
+        assert!(emulator.input[0x0]);
+        assert!(emulator.input[0x5]);
+        assert!(emulator.input[0xF]);
+        assert!(!emulator.input[0x3]);
+        assert_eq!(emulator.keypad(), 0b1000_0000_0010_0001);
+    }
+
+    #[test]
+    fn should_execute_display_sprite_with_vf_as_coordinate() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0xF] = 30;
+        emulator.cpu.registers[0x3] = 10;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0b11000000;
+        emulator.active_pixels.insert((31, 10));
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 0xF,
+            register_y: 0x3,
+            n_bytes: 1,
+        });
+
+        // Then
+        assert_eq!(emulator.active_pixels.len(), 1);
+        assert!(emulator.active_pixels.contains(&(30, 10)));
+        assert_eq!(emulator.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn should_keep_flag_in_vf_when_vf_is_destination() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0xF] = 0x80;
+
+            // When
+            emulator.execute(AddRegToReg {
+                register_lhs: 0xF,
+                register_rhs: 0xF,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0xF] = 0x10;
+            emulator.cpu.registers[0x1] = 0x20;
+
+            // When
+            emulator.execute(SubReg2FromReg1 {
+                register_lhs: 0xF,
+                register_rhs: 0x1,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.registers[0xF], 0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0xF] = 0b11;
+
+            // When
+            emulator.execute(BitwiseShrBy1 {
+                register_lhs: 0xF,
+                register_rhs: 0xF,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0xF] = 0b1000_0001;
+
+            // When
+            emulator.execute(BitwiseShlBy1 {
+                register_lhs: 0xF,
+                register_rhs: 0xF,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.registers[0xF], 1);
+        }
+    }
+
+    #[test]
+    fn should_pack_sprite_straddling_bytes_into_frame_buffer() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x1] = 12;
+        emulator.cpu.registers[0x2] = 31;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0b10110011;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 0x1,
+            register_y: 0x2,
+            n_bytes: 1,
+        });
+        let bytes = emulator.frame_buffer();
+
+        // Then
+        assert_eq!(bytes.len(), 256);
+        assert_eq_hex!(bytes[31 * 8 + 1], 0b00001011);
+        assert_eq_hex!(bytes[31 * 8 + 2], 0b00110000);
+        assert_eq!(bytes.iter().filter(|byte| **byte != 0).count(), 2);
+    }
+
+    #[test]
+    fn should_execute_bitwise_shr_by_1_copying_from_vy() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_shift_quirk(ShiftQuirk::CopyFromVy);
+        emulator.cpu.registers[0x3] = 0b11110000;
+        emulator.cpu.registers[0x5] = 0b10001101;
+
+        // When
+        emulator.execute(BitwiseShrBy1 {
+            register_lhs: 0x3,
+            register_rhs: 0x5,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0b1000110);
+        assert_eq_hex!(emulator.cpu.registers[0x5], 0b10001101);
+        assert_eq!(emulator.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn should_execute_bitwise_shl_by_1_copying_from_vy() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_shift_quirk(ShiftQuirk::CopyFromVy);
+        emulator.cpu.registers[0x3] = 0b00001111;
+        emulator.cpu.registers[0x5] = 0b10001101;
+
+        // When
+        emulator.execute(BitwiseShlBy1 {
+            register_lhs: 0x3,
+            register_rhs: 0x5,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0b00011010);
+        assert_eq_hex!(emulator.cpu.registers[0x5], 0b10001101);
+        assert_eq!(emulator.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn should_execute_jump_with_vx_offset() {
+        use Instruction::*;
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.cpu.registers[0x0] = 0x10;
+            emulator.cpu.registers[0x3] = 0x20;
+
+            // When
+            emulator.execute(JumpWithV0Offset { address: 0x3A0 });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.program_counter, 0x3B0);
+        }
+
+        {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.set_jump_quirk(JumpQuirk::VxOffset);
+            emulator.cpu.registers[0x0] = 0x10;
+            emulator.cpu.registers[0x3] = 0x20;
+
+            // When
+            emulator.execute(JumpWithV0Offset { address: 0x3A0 });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.program_counter, 0x3C0);
+        }
+    }
+
+    #[test]
+    fn should_wrap_jump_with_offset_within_address_space() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_jump_quirk(JumpQuirk::VxOffset);
+        emulator.cpu.registers[0xF] = 0x30;
+
+        // When
+        emulator.execute(JumpWithV0Offset { address: 0xFE0 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.program_counter, 0x010);
+    }
+
+    #[test]
+    fn should_apply_memory_increment_quirk_after_store_registers() {
+        use Instruction::*;
+
+        for (memory_increment_quirk, expected_register_i) in [
+            (MemoryIncrementQuirk::Unchanged, 0x765),
+            (MemoryIncrementQuirk::IncrementByX, 0x765 + 5),
+            (MemoryIncrementQuirk::IncrementByXPlus1, 0x765 + 6),
+        ] {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.set_memory_increment_quirk(memory_increment_quirk);
+            emulator.cpu.register_i = 0x765;
+
+            // When
+            emulator.execute(StoreRegisters { last_register: 5 });
+
+            // Then
+            assert_eq_hex!(emulator.cpu.register_i, expected_register_i);
+        }
+    }
+
+    #[test]
+    fn should_apply_memory_increment_quirk_after_load_registers() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_memory_increment_quirk(MemoryIncrementQuirk::IncrementByXPlus1);
+        emulator.cpu.register_i = 0x765;
+        emulator.memory[0x765 + 2] = 0x42;
+
+        // When
+        emulator.execute(LoadRegisters { last_register: 2 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x2], 0x42);
+        assert_eq_hex!(emulator.cpu.register_i, 0x765 + 3);
+    }
+
+    #[test]
+    fn should_clip_display_sprite_at_right_edge() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_wrap_mode(WrapMode::Clip);
+        emulator.cpu.registers[2] = 60;
+        emulator.cpu.registers[3] = 4;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0xFF;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 1,
+        });
+
+        // Then
+        let expected: Pixels = (60..64).map(|x| (x, 4)).collect();
+        assert_eq!(emulator.active_pixels, expected);
+    }
+
+    #[test]
+    fn should_wrap_display_sprite_around_edges() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_wrap_mode(WrapMode::Wrap);
+        emulator.cpu.registers[2] = 60;
+        emulator.cpu.registers[3] = (SCREEN_HEIGHT - 1) as u8;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0xFF;
+        emulator.memory[0x601] = 0x81;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 2,
+        });
+
+        // Then
+        let mut expected: Pixels = (60..64)
+            .chain(0..4)
+            .map(|x| (x, SCREEN_HEIGHT - 1))
+            .collect();
+        expected.insert((60, 0));
+        expected.insert((3, 0));
+        assert_eq!(emulator.active_pixels, expected);
+    }
+
+    #[test]
+    fn should_not_collide_with_pixels_under_clipped_part_of_sprite() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_wrap_mode(WrapMode::Clip);
+        emulator.active_pixels.insert((0, SCREEN_HEIGHT - 1));
+        emulator.active_pixels.insert((60, 0));
+        emulator.cpu.registers[2] = 60;
+        emulator.cpu.registers[3] = (SCREEN_HEIGHT - 1) as u8;
+        emulator.cpu.registers[0xF] = 1;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0xFF;
+        emulator.memory[0x601] = 0xFF;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 2,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0xF], 0);
+        assert!(emulator.active_pixels.contains(&(0, SCREEN_HEIGHT - 1)));
+        assert!(emulator.active_pixels.contains(&(60, 0)));
+        assert!(emulator.active_pixels.contains(&(63, SCREEN_HEIGHT - 1)));
+    }
+
+    #[test]
+    fn should_collide_with_pixels_under_wrapped_part_of_sprite() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_wrap_mode(WrapMode::Wrap);
+        emulator.active_pixels.insert((0, SCREEN_HEIGHT - 1));
+        emulator.cpu.registers[2] = 60;
+        emulator.cpu.registers[3] = (SCREEN_HEIGHT - 1) as u8;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0xFF;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 2,
+            register_y: 3,
+            n_bytes: 1,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0xF], 1);
+        assert!(!emulator.active_pixels.contains(&(0, SCREEN_HEIGHT - 1)));
+        assert!(emulator.active_pixels.contains(&(1, SCREEN_HEIGHT - 1)));
+    }
+
+    #[test]
+    fn should_wrap_program_counter_at_end_of_memory() {
+        // Given
+        let mut emulator = Emulator::new();
+        let mut program = vec![0u8; MEMORY_SIZE - 512];
+        program[MEMORY_SIZE - 512 - 2] = 0x6A;
+        program[MEMORY_SIZE - 512 - 1] = 0x42;
+        emulator.load_program_from_data(&program).unwrap();
+        emulator.set_pc(0xFFE).unwrap();
+
+        // When
+        emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0xA], 0x42);
+        assert_eq_hex!(emulator.program_counter(), 0x000);
+        assert!(!emulator.is_halted());
+    }
+
+    #[test]
+    fn should_wrap_program_counter_when_skipping_past_end_of_memory() {
+        use Instruction::*;
+
+        for (memory_size, address) in [(MEMORY_SIZE, 0xFFC), (XO_CHIP_MEMORY_SIZE, 0xFFFC)] {
+            // Given
+            let mut emulator = Emulator::builder()
+                .memory_size(memory_size)
+                .build()
+                .unwrap();
+            emulator.cpu.program_counter = address;
+
+            // When
+            emulator.execute(SkipIfRegEqConstant {
+                register: 0,
+                constant: 0,
+            });
+
+            // Then
+            assert_eq_hex!(emulator.program_counter(), 0x000);
+        }
+    }
+
+    #[test]
+    fn should_stop_fetching_instruction_straddling_end_of_memory() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.program_counter = 0xFFF;
+        emulator.set_log_level(LogLevel::Off);
+
+        // When
+        emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert!(emulator.is_halted());
+        assert!(matches!(
+            emulator.last_error(),
+            Some(Chip8Error::AddressOutOfBounds(0xFFF))
+        ));
+    }
+
+    #[test]
+    fn should_wrap_memory_accesses_past_end_of_memory() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.register_i = 0xFFE;
+        emulator.cpu.registers[0x0] = 0x11;
+        emulator.cpu.registers[0x1] = 0x22;
+        emulator.cpu.registers[0x2] = 0x33;
+
+        // When
+        emulator.execute(StoreRegisters { last_register: 2 });
+
+        // Then
+        assert_eq_hex!(emulator.memory[0xFFE], 0x11);
+        assert_eq_hex!(emulator.memory[0xFFF], 0x22);
+        assert_eq_hex!(emulator.memory[0x000], 0x33);
+    }
+
+    #[test]
+    fn should_halt_on_stack_overflow() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_log_level(LogLevel::Off);
+        emulator.load_instructions(vec![Call { address: 0x200 }]);
+
+        // When
+        for _ in 0..17 {
+            emulator.step(Duration::from_millis(2));
+        }
+
+        // Then
+        assert!(emulator.is_halted());
+        assert!(matches!(
+            emulator.last_error(),
+            Some(Chip8Error::StackOverflow)
+        ));
+        assert_eq!(emulator.cpu.stack_index, 15);
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+    }
+
+    #[test]
+    fn should_halt_on_stack_underflow() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_log_level(LogLevel::Off);
+        emulator.load_instructions(vec![Return]);
+
+        // When
+        emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert!(emulator.is_halted());
+        assert!(matches!(
+            emulator.last_error(),
+            Some(Chip8Error::StackUnderflow)
+        ));
+        assert_eq!(emulator.cpu.stack_index, -1);
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+    }
+
+    #[test]
+    fn should_fail_to_load_too_large_program() {
+        // Given
+        let mut emulator = Emulator::new();
+        let program = vec![0xAB; MEMORY_SIZE - 512 + 1];
+
+        // When
+        let result = emulator.load_program_from_data(&program);
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(Chip8Error::ProgramTooLarge {
+                size: 3585,
+                capacity: 3584
+            })
+        ));
+        assert_eq!(emulator.memory[512], 0);
+    }
+
+    #[test]
+    fn should_fail_to_load_nonexistent_file() {
+        // Given
+        let mut emulator = Emulator::new();
+
+        // When
+        let result = emulator.load_program_from_file("this/rom/does/not/exist.ch8");
+
+        // Then
+        assert!(matches!(result, Err(Chip8Error::Io(_))));
+    }
+
+    #[test]
+    fn should_generate_same_random_numbers_for_same_seed() {
+        use Instruction::*;
+
+        // Given
+        let mut emulators = [Emulator::with_seed(42), Emulator::with_seed(42)];
+
+        // When
+        for emulator in emulators.iter_mut() {
+            emulator.execute(BitwiseAndWithRand {
+                register: 0,
+                constant: 0xFF,
+            });
+            emulator.execute(BitwiseAndWithRand {
+                register: 1,
+                constant: 0xFF,
+            });
+            emulator.execute(BitwiseAndWithRand {
+                register: 2,
+                constant: 0x0F,
+            });
+            emulator.execute(BitwiseAndWithRand {
+                register: 3,
+                constant: 0xF0,
+            });
+        }
+
+        // Then
+        assert_eq_hex!(emulators[0].cpu.registers, emulators[1].cpu.registers);
+        assert_eq_hex!(emulators[0].cpu.registers[2] & 0xF0, 0);
+        assert_eq_hex!(emulators[0].cpu.registers[3] & 0x0F, 0);
+    }
+
+    #[test]
+    fn should_step_exactly_one_instruction() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            SetRegToConstant {
+                register: 0,
+                constant: 0x12,
+            },
+            SkipIfRegEqConstant {
+                register: 0,
+                constant: 0x12,
+            },
+            ClearDisplay,
+            Jump { address: 0x200 },
+        ]);
+
+        // When
+        let first = emulator.step_instruction();
+        let first_pc = emulator.program_counter();
+        let second = emulator.step_instruction();
+        let second_pc = emulator.program_counter();
+        let third = emulator.step_instruction();
+
+        // Then
+        assert_eq!(
+            first,
+            Some(SetRegToConstant {
+                register: 0,
+                constant: 0x12
+            })
+        );
+        assert_eq_hex!(first_pc, 0x202);
+        assert_eq!(
+            second,
+            Some(SkipIfRegEqConstant {
+                register: 0,
+                constant: 0x12
+            })
+        );
+        assert_eq_hex!(second_pc, 0x206);
+        assert_eq!(third, Some(Jump { address: 0x200 }));
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+    }
+
+    #[test]
+    fn should_step_over_call() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            Call { address: 0x206 },
+            SetRegToConstant {
+                register: 1,
+                constant: 0x05,
+            },
+            Jump { address: 0x204 },
+            SetRegToConstant {
+                register: 0,
+                constant: 0x07,
+            },
+            Return,
+        ]);
+
+        // When
+        let instruction = emulator.step_over();
+
+        // Then
+        assert_eq!(instruction, Some(Call { address: 0x206 }));
+        assert_eq_hex!(emulator.program_counter(), 0x202);
+        assert_eq!(emulator.cpu.stack_index, -1);
+        assert_eq_hex!(emulator.cpu.registers[0], 0x07);
+        assert_eq_hex!(emulator.cpu.registers[1], 0x00);
+    }
+
+    #[test]
+    fn should_step_over_recursive_call_returning_to_same_address() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            SetRegToConstant {
+                register: 0,
+                constant: 3,
+            },
+            Call { address: 0x206 },
+            Jump { address: 0x204 },
+            AddConstToReg {
+                register: 1,
+                constant: 1,
+            },
+            AddConstToReg {
+                register: 0,
+                constant: 0xFF,
+            },
+            SkipIfRegEqConstant {
+                register: 0,
+                constant: 0,
+            },
+            Call { address: 0x206 },
+            Return,
+        ]);
+        emulator.run_instructions(5);
+        assert_eq_hex!(emulator.program_counter(), 0x20C);
+
+        // When
+        emulator.step_over();
+
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x20E);
+        assert_eq!(emulator.cpu.stack_index, 0);
+        assert_eq!(emulator.cpu.registers[0], 0);
+        assert_eq!(emulator.cpu.registers[1], 3);
+    }
+
+    #[test]
+    fn should_step_over_other_instructions_like_step_instruction() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![Jump { address: 0x206 }]);
+
+        // When
+        let instruction = emulator.step_over();
+
+        // Then
+        assert_eq!(instruction, Some(Jump { address: 0x206 }));
+        assert_eq_hex!(emulator.program_counter(), 0x206);
+    }
+
+    #[test]
+    fn should_stop_step_over_at_breakpoint_in_subroutine() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            Call { address: 0x204 },
+            Jump { address: 0x202 },
+            ClearDisplay,
+            Return,
+        ]);
+        emulator.add_breakpoint(0x206);
+
+        // When
+        emulator.step_over();
+
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x206);
+        assert_eq!(emulator.cpu.stack_index, 0);
+    }
+
+    #[test]
+    fn should_not_step_instruction_when_halted() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.program_counter = 0x0FFF;
+
+        // When
+        let first = emulator.step_instruction();
+        let second = emulator.step_instruction();
+
+        // Then
+        assert_eq!(first, None);
+        assert_eq!(second, None);
+        assert!(emulator.is_halted());
+    }
+
+    #[test]
+    fn should_stop_on_breakpoint_before_executing_instruction() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            SetRegToConstant {
+                register: 0,
+                constant: 0x01,
+            },
+            SetRegToConstant {
+                register: 1,
+                constant: 0x02,
+            },
+            Jump { address: 0x204 },
+        ]);
+        emulator.add_breakpoint(0x202);
+
+        // When
+        let first = emulator.step(Duration::from_millis(2));
+        let second = emulator.step(Duration::from_millis(2));
+        let pc_at_breakpoint = emulator.program_counter();
+        let third = emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert_eq!(first, StepStatus::Running);
+        assert_eq!(second, StepStatus::BreakpointHit(0x202));
+        assert_eq_hex!(pc_at_breakpoint, 0x202);
+        assert_eq_hex!(emulator.cpu.registers[1], 0x02);
+        assert_eq!(third, StepStatus::Running);
+        assert_eq_hex!(emulator.program_counter(), 0x204);
+    }
+
+    #[test]
+    fn should_stop_after_write_to_watched_memory() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            SetRegToConstant {
+                register: 0,
+                constant: 0x01,
+            },
+            SetRegToConstant {
+                register: 1,
+                constant: 0x02,
+            },
+            SetAddress { address: 0x300 },
+            StoreRegisters { last_register: 1 },
+            Jump { address: 0x208 },
+        ]);
+        emulator.memory[0x301] = 0x07;
+        emulator.add_memory_watch(0x301);
+
+        // When
+        let first = emulator.step(Duration::from_millis(10));
+        let pc_at_watch_hit = emulator.program_counter();
+        let second = emulator.step(Duration::from_millis(10));
+
+        // Then
+        assert_eq!(
+            first,
+            StepStatus::WatchHit(MemoryWrite {
+                address: 0x301,
+                old_value: 0x07,
+                new_value: 0x02,
+            })
+        );
+        assert_eq_hex!(pc_at_watch_hit, 0x208);
+        assert_eq!(second, StepStatus::Running);
+    }
+
+    #[test]
+    fn should_not_stop_after_write_leaving_watched_memory_unchanged() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            SetRegToConstant {
+                register: 0,
+                constant: 123,
+            },
+            SetAddress { address: 0x300 },
+            StoreRegBcd { register: 0 },
+            StoreRegBcd { register: 0 },
+            Jump { address: 0x208 },
+        ]);
+        emulator.add_memory_watch(0x302);
+
+        // When
+        let first = emulator.step(Duration::from_millis(10));
+        let second = emulator.step(Duration::from_millis(10));
+
+        // Then
+        assert_eq!(
+            first,
+            StepStatus::WatchHit(MemoryWrite {
+                address: 0x302,
+                old_value: 0,
+                new_value: 3,
+            })
+        );
+        assert_eq!(second, StepStatus::Running);
+        assert_eq_hex!(emulator.program_counter(), 0x208);
+    }
+
+    #[test]
+    fn should_not_stop_on_removed_breakpoint() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![Jump { address: 0x200 }]);
+        emulator.add_breakpoint(0x200);
+        emulator.remove_breakpoint(0x200);
+
+        // When
+        let status = emulator.step(Duration::from_millis(2));
+
+        // Then
+        assert_eq!(status, StepStatus::Running);
+    }
+
+    #[test]
+    fn should_execute_instructions_due_at_cpu_clock() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            AddConstToReg {
+                register: 0,
+                constant: 1
+            };
+            32
+        ]);
+        emulator.set_cpu_clock(CpuClock::Hz(1000));
+
+        // When
+        emulator.step(Duration::from_millis(10));
+        let executed_in_10ms = emulator.cpu.registers[0];
+        emulator.step(Duration::from_micros(2500));
+        let executed_in_12_5ms = emulator.cpu.registers[0];
+        emulator.step(Duration::from_micros(500));
+
+        // Then
+        assert_eq!(executed_in_10ms, 10);
+        assert_eq!(executed_in_12_5ms, 13);
+        assert_eq!(emulator.cpu.registers[0], 13);
+    }
+
+    #[test]
+    fn should_execute_one_instruction_per_step_when_unthrottled() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            AddConstToReg {
+                register: 0,
+                constant: 1
+            };
+            32
+        ]);
+        emulator.set_cpu_clock(CpuClock::Unthrottled);
+
+        // When
+        for _ in 0..3 {
+            emulator.step(Duration::ZERO);
+        }
+        emulator.step(Duration::from_secs(1));
+
+        // Then
+        assert_eq!(emulator.cpu.registers[0], 4);
+    }
+
+    #[test]
+    fn should_catch_up_on_instructions_after_long_step() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            AddConstToReg {
+                register: 0,
+                constant: 1
+            };
+            128
+        ]);
+
+        // When
+        emulator.step(Duration::from_millis(100));
+
+        // Then
+        assert_eq!(emulator.cpu.registers[0], 50);
+    }
+
+    #[test]
+    fn should_drop_cycles_past_catch_up_limit() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            AddConstToReg {
+                register: 0,
+                constant: 1
+            };
+            256
+        ]);
+
+        // When
+        emulator.step(Duration::from_secs(10));
+
+        // Then
+        assert_eq!(emulator.cpu.registers[0], 125);
+    }
+
+    #[test]
+    fn should_decrement_timers_once_per_elapsed_60hz_tick() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.halted = true;
+        emulator.cpu.delay_timer = 10;
+        emulator.cpu.sound_timer = 2;
+
+        // When
+        emulator.step(Duration::from_millis(50));
+
+        // Then
+        assert_eq!(emulator.cpu.delay_timer, 7);
+        assert_eq!(emulator.cpu.sound_timer, 0);
+
+        // When
+        emulator.step(Duration::from_millis(10));
+        emulator.step(Duration::from_millis(7));
+
+        // Then
+        assert_eq!(emulator.cpu.delay_timer, 6);
+    }
+
+    #[test]
+    fn should_pack_active_pixels_into_frame_buffer() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .active_pixels
+            .extend([(0, 0), (9, 0), (63, 0), (3, 31)]);
+
+        // When
+        let frame_buffer = emulator.frame_buffer();
+
+        // Then
+        assert_eq_hex!(frame_buffer[0], 0x80);
+        assert_eq_hex!(frame_buffer[1], 0x40);
+        assert_eq_hex!(frame_buffer[7], 0x01);
+        assert_eq_hex!(frame_buffer[31 * 8], 0x10);
+        assert_eq!(frame_buffer.iter().filter(|byte| **byte != 0).count(), 4);
+    }
+
+    #[test]
+    fn should_reset_to_pristine_state_keeping_program() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![
+            SetRegToConstant {
+                register: 0,
+                constant: 0x0A,
+            },
+            SetAddress { address: 0x300 },
+            SetDelayTimer { register: 0 },
+            Call { address: 0x20A },
+            ClearDisplay,
+            StoreRegBcd { register: 0 },
+        ]);
+        emulator.execute(HighResolution);
+        emulator.cpu.program_counter = 0x200;
+        emulator.active_pixels.insert((1, 2));
+        emulator.input[3] = true;
+        for _ in 0..6 {
+            emulator.step_instruction();
+        }
+
+        // When
+        emulator.reset();
+
+        // Then
+        assert_eq!(emulator.cpu.registers, [0; 16]);
+        assert_eq_hex!(emulator.cpu.register_i, 0);
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+        assert_eq!(emulator.cpu.stack_index, -1);
+        assert_eq!(emulator.cpu.stack, [0; 16]);
+        assert_eq!(emulator.cpu.delay_timer, 0);
+        assert!(emulator.active_pixels.is_empty());
+        assert_eq!(emulator.active_pixels.resolution(), Resolution::Low);
+        assert_eq!(emulator.second_plane.resolution(), Resolution::Low);
+        assert_eq!(emulator.input, [false; 16]);
+        assert_eq!(emulator.memory[..FONT_SPRITES.len()], FONT_SPRITES);
+        assert_eq_hex!(emulator.opcode_at(0x200), Some(0x600A));
+        assert_eq_hex!(emulator.opcode_at(0x20A), Some(0xF033));
+        assert_eq_hex!(emulator.memory[0x301], 0x01);
+    }
+
+    #[test]
+    fn should_restore_program_memory_on_reset_and_reload() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[0x12, 0x00]).unwrap();
+        emulator.memory[0x200] = 0xFF;
+        emulator.memory[0x300] = 0xAB;
+
+        // When
+        emulator.reset_and_reload();
+
+        // Then
+        assert_eq_hex!(emulator.opcode_at(0x200), Some(0x1200));
+        assert_eq_hex!(emulator.memory[0x300], 0);
+    }
+
+    #[test]
+    fn should_replace_running_program_on_reset_and_load() {
+        // Given
+        let path = std::env::temp_dir().join(format!("chip8-rom-{}.ch8", std::process::id()));
+        fs::write(&path, [0x60, 0x05]).unwrap();
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program_from_data(&[0x61, 0x07, 0x62, 0x09])
+            .unwrap();
+        emulator.step(Duration::from_millis(2));
+
+        // When
+        let result = emulator.reset_and_load_program_from_file(&path);
+        emulator.step(Duration::from_millis(2));
+        fs::remove_file(&path).unwrap();
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq_hex!(emulator.opcode_at(0x200), Some(0x6005));
+        assert_eq_hex!(emulator.opcode_at(0x202), Some(0x0000));
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x05);
+        assert_eq_hex!(emulator.cpu.registers[0x1], 0x00);
+        assert_eq_hex!(emulator.program_counter(), 0x202);
+    }
+
+    #[test]
+    fn should_keep_running_program_when_reset_and_load_fails() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[0x12, 0x00]).unwrap();
+
+        // When
+        let result = emulator.reset_and_load_program_from_data(&vec![0; MEMORY_SIZE]);
+
+        // Then
+        assert!(matches!(result, Err(Chip8Error::ProgramTooLarge { .. })));
+        assert_eq_hex!(emulator.opcode_at(0x200), Some(0x1200));
+    }
+
+    #[test]
+    fn should_load_odd_length_program_by_default() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_log_level(LogLevel::Off);
+
+        // When
+        let result = emulator.load_program_from_data(&[0x60, 0x05, 0x70]);
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq!(emulator.program_len(), 3);
+        assert_eq_hex!(emulator.opcode_at(0x202), Some(0x7000));
+    }
+
+    #[test]
+    fn should_reject_odd_length_program_with_strict_program_length() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_strict_program_length(true);
+
+        // When
+        let result = emulator.load_program_from_data(&[0x60, 0x05, 0x70]);
+
+        // Then
+        assert!(matches!(result, Err(Chip8Error::OddProgramLength(3))));
+        assert_eq!(emulator.program_len(), 0);
+        assert_eq_hex!(emulator.opcode_at(0x200), Some(0x0000));
+    }
+
+    #[test]
+    fn should_load_byte_swapped_program_with_byte_swap_on_load() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_byte_swap_on_load(true);
+
+        // When
+        emulator
+            .load_program_from_data(&[0x05, 0x60, 0x34, 0xA1, 0x70])
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            emulator.step_instruction(),
+            Some(Instruction::SetRegToConstant {
+                register: 0,
+                constant: 0x05
+            })
+        );
+        assert_eq_hex!(emulator.opcode_at(0x202), Some(0xA134));
+        assert_eq_hex!(emulator.memory[0x204], 0x70);
+    }
+
+    #[test]
+    fn should_halt_on_odd_program_counter_with_strict_alignment() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_strict_alignment(true);
+        emulator
+            .load_program_from_data(&[0x60, 0x03, 0xB3, 0x00])
+            .unwrap();
+
+        // When
+        let executed_instructions = emulator.run_instructions(3);
+
+        // Then
+        assert_eq!(executed_instructions, 2);
+        assert!(emulator.is_halted());
+        assert_eq_hex!(emulator.cpu.program_counter, 0x303);
+        assert!(matches!(
+            emulator.last_error(),
+            Some(Chip8Error::MisalignedProgramCounter(0x303))
+        ));
+    }
+
+    #[test]
+    fn should_allow_odd_program_counter_by_default() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program_from_data(&[0x60, 0x03, 0xB3, 0x00])
+            .unwrap();
+
+        // When
+        let executed_instructions = emulator.run_instructions(3);
+
+        // Then
+        assert_eq!(executed_instructions, 3);
+        assert!(!emulator.is_halted());
+        assert_eq_hex!(emulator.cpu.program_counter, 0x305);
+    }
+
+    #[test]
+    fn should_load_program_from_reader() {
+        // Given
+        let mut emulator = Emulator::new();
+        let reader = std::io::Cursor::new(vec![0x00, 0xE0, 0x12, 0x00]);
+
+        // When
+        let result = emulator.load_program_from_reader(reader);
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq_hex!(emulator.opcode_at(0x200), Some(0x00E0));
+        assert_eq_hex!(emulator.opcode_at(0x202), Some(0x1200));
+    }
+
+    #[test]
+    fn should_load_program_from_slice() {
+        // Given
+        let mut emulator = Emulator::new();
+        let rom: [u8; 6] = [0xAA, 0xBB, 0x60, 0x0F, 0x12, 0x02];
+
+        // When
+        let result = emulator.load_program_from_data(&rom[2..]);
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq_hex!(emulator.opcode_at(0x200), Some(0x600F));
+        assert_eq_hex!(emulator.opcode_at(0x202), Some(0x1202));
+    }
+
+    #[test]
+    fn should_fail_to_load_too_large_program_from_reader() {
+        // Given
+        let mut emulator = Emulator::new();
+        let reader = std::io::repeat(0xAB).take(MEMORY_SIZE as u64);
+
+        // When
+        let result = emulator.load_program_from_reader(reader);
+
+        // Then
+        assert!(matches!(result, Err(Chip8Error::ProgramTooLarge { .. })));
+    }
+
+    #[test]
+    fn should_switch_resolution_and_clear_display() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.active_pixels.insert((1, 1));
+
+        // When
+        emulator.execute(HighResolution);
+
+        // Then
+        assert_eq!(emulator.resolution(), Resolution::High);
+        assert_eq!(emulator.active_pixels.width(), 128);
+        assert_eq!(emulator.active_pixels.height(), 64);
+        assert!(emulator.active_pixels.is_empty());
+        assert_eq!(emulator.frame_buffer().len(), 1024);
+
+        // When
+        emulator.active_pixels.insert((100, 50));
+        emulator.execute(LowResolution);
+
+        // Then
+        assert_eq!(emulator.resolution(), Resolution::Low);
+        assert!(emulator.active_pixels.is_empty());
+        assert_eq!(emulator.frame_buffer().len(), 256);
+    }
+
+    #[test]
+    fn should_draw_sprite_at_high_resolution_coordinates() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_wrap_mode(WrapMode::Wrap);
+        emulator.execute(HighResolution);
+        emulator.cpu.registers[0] = 124;
+        emulator.cpu.registers[1] = 63;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600] = 0b11000011;
+        emulator.memory[0x601] = 0b10000001;
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 0,
+            register_y: 1,
+            n_bytes: 2,
+        });
+
+        // Then
+        let pixels: Vec<(u32, u32)> = emulator.active_pixels.iter().collect();
+        assert_eq!(
+            pixels,
+            vec![(3, 0), (124, 0), (2, 63), (3, 63), (124, 63), (125, 63)]
+        );
+        assert_eq_hex!(emulator.frame_buffer()[63 * 16 + 15], 0b0000_1100);
+    }
+
+    #[test]
+    fn should_draw_16x16_sprite_in_high_resolution() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.execute(HighResolution);
+        emulator.cpu.registers[0] = 100;
+        emulator.cpu.registers[1] = 40;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600..0x620].fill(0xFF);
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 0,
+            register_y: 1,
+            n_bytes: 0,
+        });
+
+        // Then
+        assert_eq!(emulator.active_pixels.len(), 256);
+        assert!(emulator.active_pixels.contains(&(100, 40)));
+        assert!(emulator.active_pixels.contains(&(115, 55)));
+        assert!(!emulator.active_pixels.contains(&(116, 55)));
+    }
+
+    #[test]
+    fn should_execute_scroll_down() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .active_pixels
+            .extend([(0, 0), (10, 5), (63, 29), (5, 31)]);
+
+        // When
+        emulator.execute(ScrollDown { n_rows: 3 });
+
+        // Then
+        let pixels: Vec<(u32, u32)> = emulator.active_pixels.iter().collect();
+        assert_eq!(pixels, vec![(0, 3), (10, 8)]);
+    }
+
+    #[test]
+    fn should_execute_scroll_right() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .active_pixels
+            .extend([(0, 0), (59, 4), (60, 4), (63, 31)]);
+
+        // When
+        emulator.execute(ScrollRight);
+
+        // Then
+        let pixels: Vec<(u32, u32)> = emulator.active_pixels.iter().collect();
+        assert_eq!(pixels, vec![(4, 0), (63, 4)]);
+    }
+
+    #[test]
+    fn should_execute_scroll_left() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.execute(HighResolution);
+        emulator
+            .active_pixels
+            .extend([(0, 0), (3, 0), (4, 10), (127, 63)]);
+
+        // When
+        emulator.execute(ScrollLeft);
+
+        // Then
+        let pixels: Vec<(u32, u32)> = emulator.active_pixels.iter().collect();
+        assert_eq!(pixels, vec![(0, 10), (123, 63)]);
+    }
+
+    #[test]
+    fn should_halve_scroll_distance_in_low_resolution_with_scroll_quirk() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_scroll_quirk(ScrollQuirk::HalvedInLowResolution);
+        emulator.active_pixels.extend([(10, 10)]);
+
+        // When
+        emulator.execute(ScrollRight);
+        emulator.execute(ScrollDown { n_rows: 4 });
+        emulator.execute(ScrollLeft);
+        emulator.execute(ScrollLeft);
+
+        // Then
+        let pixels: Vec<(u32, u32)> = emulator.active_pixels.iter().collect();
+        assert_eq!(pixels, vec![(8, 12)]);
+    }
+
+    #[test]
+    fn should_draw_16x16_sprite_for_zero_rows() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0] = 8;
+        emulator.cpu.registers[1] = 4;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600..0x620].fill(0xFF);
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 0,
+            register_y: 1,
+            n_bytes: 0,
+        });
+        let first_vf = emulator.cpu.registers[0xF];
+        let pixels_after_first = emulator.active_pixels.len();
+        emulator.cpu.registers[0] = 16;
+        emulator.execute(DisplaySprite {
+            register_x: 0,
+            register_y: 1,
+            n_bytes: 0,
+        });
+
+        // Then
+        assert_eq!(pixels_after_first, 256);
+        assert_eq!(first_vf, 0);
+        assert_eq!(emulator.active_pixels.len(), 256);
+        assert_eq!(emulator.cpu.registers[0xF], 1);
+    }
+
+    #[test]
+    fn should_count_collided_and_clipped_rows_in_high_resolution_with_collision_quirk() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_collision_quirk(CollisionQuirk::CountRows);
+        emulator.execute(HighResolution);
+        emulator.active_pixels.extend([(10, 52), (11, 55)]);
+        emulator.cpu.registers[0] = 10;
+        emulator.cpu.registers[1] = 52;
+        emulator.cpu.register_i = 0x600;
+        emulator.memory[0x600..0x620].fill(0xFF);
+
+        // When
+        emulator.execute(DisplaySprite {
+            register_x: 0,
+            register_y: 1,
+            n_bytes: 0,
+        });
+
+        // Then
+        assert_eq!(emulator.cpu.registers[0xF], 2 + 4);
+        assert_eq!(emulator.active_pixels.len(), 12 * 16 - 2);
+    }
+
+    #[test]
+    fn should_only_flag_collision_of_big_sprite_unless_counting_rows_in_high_resolution() {
+        use Instruction::*;
+
+        for (preset, high_resolution, expected_flag) in [
+            (QuirkPreset::SuperChip, true, 2 + 4),
+            (QuirkPreset::SuperChip, false, 1),
+            (QuirkPreset::XoChip, true, 1),
+        ] {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.set_quirk_preset(preset);
+            if high_resolution {
+                emulator.execute(HighResolution);
+            }
+            let bottom = emulator.active_pixels.height() - 12;
+            emulator
+                .active_pixels
+                .extend([(10, bottom), (11, bottom + 3)]);
+            emulator.cpu.registers[0] = 10;
+            emulator.cpu.registers[1] = bottom as u8;
+            emulator.cpu.register_i = 0x600;
+            emulator.memory[0x600..0x620].fill(0xFF);
+
+            // When
+            emulator.execute(DisplaySprite {
+                register_x: 0,
+                register_y: 1,
+                n_bytes: 0,
+            });
+
+            // Then
+            assert_eq!(emulator.cpu.registers[0xF], expected_flag, "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn should_draw_big_font_char() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x0] = 0x21;
+        emulator.cpu.registers[0x1] = 10;
+        emulator.cpu.registers[0x2] = 3;
+
+        // When
+        emulator.execute(SetAddressOfBigFontChar { register: 0x0 });
+        emulator.execute(DisplaySprite {
+            register_x: 0x1,
+            register_y: 0x2,
+            n_bytes: 10,
+        });
+
+        // Then
+        #[rustfmt::skip]
+        let expected_rows = [
+            "...##...",
+            ".####...",
+            ".####...",
+            "...##...",
+            "...##...",
+            "...##...",
+            "...##...",
+            "...##...",
+            "########",
+            "########",
+        ];
+        for (y, row) in expected_rows.iter().enumerate() {
+            for (x, pixel) in row.chars().enumerate() {
+                let position = (10 + x as u32, 3 + y as u32);
+                assert_eq!(emulator.active_pixels.contains(&position), pixel == '#');
+            }
+        }
+        assert_eq_hex!(emulator.cpu.register_i, 0x5A);
+    }
+
+    #[test]
+    fn should_draw_only_on_selected_plane() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.memory[0x300] = 0b1000_0000;
+        emulator.memory[0x301] = 0b0100_0000;
+        emulator.cpu.register_i = 0x300;
+
+        // When
+        emulator.execute(SelectPlanes { planes: 0b10 });
+        emulator.execute(DisplaySprite {
+            register_x: 0x0,
+            register_y: 0x0,
+            n_bytes: 1,
+        });
+
+        // Then
+        assert_eq!(emulator.selected_planes(), 0b10);
+        assert!(emulator.active_pixels.is_empty());
+        assert_eq!(emulator.second_plane, Pixels::from_iter([(0, 0)]));
+    }
+
+    #[test]
+    fn should_draw_consecutive_sprite_data_on_both_planes() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.memory[0x300] = 0b1000_0000;
+        emulator.memory[0x301] = 0b0100_0000;
+        emulator.cpu.register_i = 0x300;
+        emulator.second_plane.insert((1, 0));
+
+        // When
+        emulator.execute(SelectPlanes { planes: 0b11 });
+        emulator.execute(DisplaySprite {
+            register_x: 0x0,
+            register_y: 0x0,
+            n_bytes: 1,
+        });
+
+        // Then
+        assert_eq!(emulator.active_pixels, Pixels::from_iter([(0, 0)]));
+        assert!(emulator.second_plane.is_empty());
+        assert_eq_hex!(emulator.cpu.registers[0xF], 0x1);
+    }
+
+    #[test]
+    fn should_clear_and_scroll_only_selected_plane() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.active_pixels.insert((1, 1));
+        emulator.second_plane.insert((2, 2));
+
+        // When
+        emulator.execute(SelectPlanes { planes: 0b01 });
+        emulator.execute(ScrollDown { n_rows: 3 });
+        emulator.execute(SelectPlanes { planes: 0b10 });
+        emulator.execute(ClearDisplay);
+
+        // Then
+        assert_eq!(emulator.active_pixels, Pixels::from_iter([(1, 4)]));
+        assert!(emulator.second_plane.is_empty());
+    }
+
+    #[test]
+    fn should_not_draw_with_no_plane_selected() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.memory[0x300] = 0xFF;
+        emulator.cpu.register_i = 0x300;
+
+        // When
+        emulator.execute(SelectPlanes { planes: 0b00 });
+        emulator.execute(DisplaySprite {
+            register_x: 0x0,
+            register_y: 0x0,
+            n_bytes: 1,
+        });
+
+        // Then
+        assert!(emulator.active_pixels.is_empty());
+        assert!(emulator.second_plane.is_empty());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_skip_whole_set_long_address() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_quirk_preset(QuirkPreset::XoChip);
+        emulator.load_program_from_data(&[
+            0x30, 0x00,             // SE V0, 0x00
+            0xF0, 0x00, 0x12, 0x34, // LD I, LONG 0x1234
+            0x40, 0x00,             // SNE V0, 0x00
+            0xF0, 0x00, 0x12, 0x34, // LD I, LONG 0x1234
+        ]).unwrap();
+
+        // When
+        emulator.step_instruction();
+
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x206);
+
+        // When
+        emulator.step_instruction();
+        emulator.step_instruction();
+
+        // Then
+        assert_eq_hex!(emulator.cpu.register_i, 0x1234);
+        assert_eq_hex!(emulator.program_counter(), 0x20C);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_execute_set_long_address() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_quirk_preset(QuirkPreset::XoChip);
+        emulator.set_wide_memory(true);
+        emulator.load_program_from_data(&[
+            0xF0, 0x00, 0x12, 0x34, // LD I, LONG 0x1234
+            0xF0, 0x00, 0xFF, 0xFE, // LD I, LONG 0xFFFE
+        ]).unwrap();
+        emulator.memory[0xFFFF] = 0xAB;
+
+        // When
+        emulator.step_instruction();
+
+        // Then
+        assert_eq_hex!(emulator.cpu.register_i, 0x1234);
+        assert_eq_hex!(emulator.program_counter(), 0x204);
+
+        // When
+        emulator.step_instruction();
+
+        // Then
+        assert_eq_hex!(emulator.cpu.register_i, 0xFFFE);
+        assert_eq_hex!(emulator.program_counter(), 0x208);
+
+        // When
+        emulator.execute(Instruction::LoadRegisters { last_register: 1 });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0x1], 0xAB);
+    }
+
+    #[test]
+    fn should_keep_memory_and_cpu_when_switching_from_xo_chip_preset() {
+        // Given
+        let mut emulator = Emulator::builder()
+            .preset(QuirkPreset::XoChip)
+            .build()
+            .unwrap();
+        emulator.cpu.program_counter = 0x2000;
+        emulator.cpu.register_i = 0x3000;
+        emulator.memory[0x3000] = 0x42;
+
+        // When
+        emulator.set_quirk_preset(QuirkPreset::CosmacVip);
+
+        // Then
+        assert!(emulator.has_wide_memory());
+        assert_eq_hex!(emulator.program_counter(), 0x2000);
+        assert_eq_hex!(emulator.cpu.register_i, 0x3000);
+        assert_eq_hex!(emulator.memory[0x3000], 0x42);
+    }
+
+    #[test]
+    fn should_build_emulator_with_memory_of_preset() {
+        assert!(Emulator::builder()
+            .preset(QuirkPreset::XoChip)
+            .build()
+            .unwrap()
+            .has_wide_memory());
+        assert!(!Emulator::builder()
+            .preset(QuirkPreset::SuperChip)
+            .build()
+            .unwrap()
+            .has_wide_memory());
+        assert!(!Emulator::new().has_wide_memory());
+    }
+
+    #[test]
+    fn should_execute_store_register_range_in_both_directions() {
+        use Instruction::*;
+
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.cpu.registers[0x3] = 0x33;
+        emulator.cpu.registers[0x4] = 0x44;
+        emulator.cpu.registers[0x5] = 0x55;
+        emulator.cpu.register_i = 0x400;
+
+        // When
+        emulator.execute(StoreRegisterRange {
+            register_first: 0x3,
+            register_last: 0x5,
+        });
+
+        // Then
+        assert_eq!(emulator.memory[0x400..0x404], [0x33, 0x44, 0x55, 0x00]);
+        assert_eq_hex!(emulator.cpu.register_i, 0x400);
+
+        // When
+        emulator.execute(StoreRegisterRange {
+            register_first: 0x5,
+            register_last: 0x3,
+        });
+
+        // Then
+        assert_eq!(emulator.memory[0x400..0x404], [0x55, 0x44, 0x33, 0x00]);
+
+        // When
+        emulator.execute(StoreRegisterRange {
+            register_first: 0x4,
+            register_last: 0x4,
+        });
+
+        // Then
+        assert_eq!(emulator.memory[0x400..0x404], [0x44, 0x44, 0x33, 0x00]);
     }
 
     #[test]
-    fn should_execute_clear_display() {
+    fn should_execute_load_register_range_in_both_directions() {
         use Instruction::*;
 
         // Given
         let mut emulator = Emulator::new();
-        emulator.active_pixels.extend([(1, 1), (10, 15), (21, 30)]);
+        emulator.memory[0x400..0x403].copy_from_slice(&[0xA1, 0xB2, 0xC3]);
+        emulator.cpu.register_i = 0x400;
 
         // When
-        emulator.execute(ClearDisplay);
+        emulator.execute(LoadRegisterRange {
+            register_first: 0x1,
+            register_last: 0x3,
+        });
 
         // Then
-        assert_eq!(emulator.active_pixels.len(), 0);
+        assert_eq!(
+            emulator.cpu.registers[0x0..0x5],
+            [0x00, 0xA1, 0xB2, 0xC3, 0x00]
+        );
+        assert_eq_hex!(emulator.cpu.register_i, 0x400);
+
+        // When
+        emulator.execute(LoadRegisterRange {
+            register_first: 0xC,
+            register_last: 0xA,
+        });
+
+        // Then
+        assert_eq!(emulator.cpu.registers[0xA..0xD], [0xC3, 0xB2, 0xA1]);
+
+        // When
+        emulator.execute(LoadRegisterRange {
+            register_first: 0xE,
+            register_last: 0xE,
+        });
+
+        // Then
+        assert_eq_hex!(emulator.cpu.registers[0xE], 0xA1);
     }
 
     #[test]
-    fn should_execute_jump() {
+    fn should_execute_load_audio_pattern() {
         use Instruction::*;
 
         // Given
         let mut emulator = Emulator::new();
+        let pattern: [u8; 16] = std::array::from_fn(|i| 0x10 + i as u8);
+        emulator.memory[0x400..0x410].copy_from_slice(&pattern);
+        emulator.cpu.register_i = 0x400;
+        assert_eq!(emulator.sound_pattern(), None);
 
         // When
-        emulator.execute(Jump { address: 0x123 });
+        emulator.execute(LoadAudioPattern);
 
         // Then
-        assert_eq_hex!(emulator.cpu.program_counter, 0x123);
+        assert_eq!(emulator.sound_pattern(), Some(&pattern));
+        assert_eq_hex!(emulator.cpu.register_i, 0x400);
     }
 
     #[test]
-    fn should_execute_call() {
+    fn should_execute_set_pitch() {
         use Instruction::*;
 
         // Given
         let mut emulator = Emulator::new();
-        let pc = emulator.cpu.program_counter;
+        assert_eq!(emulator.playback_rate(), 4000.0);
+        emulator.cpu.registers[0x2] = 112;
 
         // When
-        emulator.execute(Call { address: 0x123 });
+        emulator.execute(SetPitch { register: 0x2 });
 
         // Then
-        assert_eq_hex!(emulator.cpu.program_counter, 0x123);
-        assert_eq!(emulator.cpu.stack_index, 0);
-        assert_eq!(
-            emulator.cpu.stack[emulator.cpu.stack_index as usize],
-            pc + 2
-        );
+        assert_eq!(emulator.playback_rate(), 8000.0);
     }
 
     #[test]
-    fn should_execute_return() {
-        use Instruction::*;
-
+    fn should_export_display_as_scaled_rgba() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.stack[0] = 0x123;
-        emulator.cpu.stack_index = 0;
+        emulator.active_pixels.insert((1, 0));
+        emulator.second_plane.insert((1, 0));
+        emulator.second_plane.insert((0, 1));
 
         // When
-        emulator.execute(Return);
+        let rgba = emulator.to_rgba(2);
 
         // Then
-        assert_eq_hex!(emulator.cpu.program_counter, 0x123);
-        assert_eq!(emulator.cpu.stack_index, -1);
+        let pixel_at = |x: usize, y: usize| &rgba[(y * 128 + x) * 4..][..4];
+        assert_eq!(rgba.len(), 128 * 64 * 4);
+        assert_eq!(pixel_at(0, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel_at(2, 0), [102, 34, 0, 255]);
+        assert_eq!(pixel_at(3, 1), [102, 34, 0, 255]);
+        assert_eq!(pixel_at(4, 1), [0, 0, 0, 255]);
+        assert_eq!(pixel_at(1, 3), [255, 102, 0, 255]);
     }
 
     #[test]
-    fn should_execute_skip_if_req_eq_constant() {
+    fn should_restore_saved_state() {
         use Instruction::*;
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x7d;
-            let pc = emulator.cpu.program_counter;
-
-            // When
-            emulator.execute(SkipIfRegEqConstant {
-                register: 0x3,
-                constant: 0x7d,
-            });
-
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 4);
-        }
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x6c;
-            let pc = emulator.cpu.program_counter;
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.execute(HighResolution);
+        emulator.cpu.registers[0x3] = 0x42;
+        emulator.cpu.register_i = 0x321;
+        emulator.memory[0x321] = 0x99;
+        emulator.active_pixels.insert((127, 63));
+        emulator.input[0x5] = true;
+        emulator.step(Duration::from_millis(20));
+        let saved = emulator.save_state();
 
-            // When
-            emulator.execute(SkipIfRegEqConstant {
-                register: 0x3,
-                constant: 0x7d,
-            });
+        // When
+        emulator.cpu.registers[0x3] = 0x00;
+        emulator.memory[0x321] = 0x00;
+        emulator.execute(LowResolution);
+        emulator.input[0x5] = false;
+        emulator.step(Duration::from_millis(7));
+        let result = emulator.load_state(&SaveState::from_bytes(&saved.to_bytes()).unwrap());
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 2);
-        }
+        // Then
+        assert!(result.is_ok());
+        assert_eq!(emulator.save_state(), saved);
+        assert_eq_hex!(emulator.cpu.registers[0x3], 0x42);
+        assert_eq_hex!(emulator.memory[0x321], 0x99);
+        assert_eq!(emulator.resolution(), Resolution::High);
+        assert!(emulator.active_pixels.contains(&(127, 63)));
+        assert!(emulator.input[0x5]);
     }
 
     #[test]
-    fn should_execute_skip_if_req_not_eq_constant() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x7d;
-            let pc = emulator.cpu.program_counter;
+    fn should_serialize_pixels_as_bitmap() {
+        // Given
+        let pixels = Pixels::from_iter([(0, 0), (63, 31)]);
 
-            // When
-            emulator.execute(SkipIfRegNotEqConstant {
-                register: 0x3,
-                constant: 0x7d,
-            });
+        // When
+        let bytes = bincode::serialize(&pixels).unwrap();
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 2);
-        }
+        // Then
+        assert_eq!(bytes.len(), 4 + 8 + 64 * 32 / 8);
+        assert_eq!(bincode::deserialize::<Pixels>(&bytes).unwrap(), pixels);
+    }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x6c;
-            let pc = emulator.cpu.program_counter;
+    #[test]
+    fn should_fail_to_load_truncated_save_state() {
+        // Given
+        let bytes = Emulator::new().save_state().to_bytes();
 
-            // When
-            emulator.execute(SkipIfRegNotEqConstant {
-                register: 0x3,
-                constant: 0x7d,
-            });
+        // When
+        let result = SaveState::from_bytes(&bytes[..bytes.len() / 2]);
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 4);
-        }
+        // Then
+        assert!(matches!(result, Err(Chip8Error::InvalidSaveState(_))));
     }
 
     #[test]
-    fn should_execute_skip_if_req_eq_req() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x42;
-            emulator.cpu.registers[0x5] = 0x42;
-            let pc = emulator.cpu.program_counter;
+    #[rustfmt::skip]
+    fn should_restore_planes_and_audio_from_saved_state() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[
+            0xF2, 0x01, // PLANE 2
+            0x60, 0x70, // LD V0, 0x70
+            0xF0, 0x3A, // PITCH V0
+            0xA2, 0x00, // LD I, 0x200
+            0xF0, 0x02, // AUDIO
+        ]).unwrap();
+        emulator.keys_pressed_while_waiting[0x7] = true;
+        let saved = emulator.save_state();
+        emulator.run_instructions(5);
+        emulator.keys_pressed_while_waiting[0x7] = false;
 
-            // When
-            emulator.execute(SkipIfRegEqReg {
-                register_lhs: 0x3,
-                register_rhs: 0x5,
-            });
+        // When
+        let result = emulator.load_state(&saved);
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 4);
-        }
+        // Then
+        assert!(result.is_ok());
+        assert_eq!(emulator.selected_planes(), 0b01);
+        assert_eq!(emulator.pitch, DEFAULT_PITCH);
+        assert_eq!(emulator.sound_pattern(), None);
+        assert!(emulator.keys_pressed_while_waiting[0x7]);
+    }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x42;
-            emulator.cpu.registers[0x5] = 0x71;
-            let pc = emulator.cpu.program_counter;
+    #[test]
+    fn should_reject_save_state_with_unsupported_memory_size() {
+        // Given
+        let mut state = Emulator::new().save_state();
+        state.memory.truncate(0x100);
+        let mut emulator = Emulator::new();
 
-            // When
-            emulator.execute(SkipIfRegEqReg {
-                register_lhs: 0x3,
-                register_rhs: 0x5,
-            });
+        // When
+        let from_bytes_result = SaveState::from_bytes(&state.to_bytes());
+        let load_result = emulator.load_state(&state);
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 2);
-        }
+        // Then
+        assert!(matches!(
+            from_bytes_result,
+            Err(Chip8Error::InvalidSaveState(_))
+        ));
+        assert!(matches!(load_result, Err(Chip8Error::InvalidSaveState(_))));
+        assert_eq!(emulator.memory.len(), MEMORY_SIZE);
+        emulator.reset();
     }
 
     #[test]
-    fn should_execute_skip_if_key_pressed() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.input[0xA] = true;
-            emulator.cpu.registers[0x3] = 0xA;
-            let pc = emulator.cpu.program_counter;
-
-            // When
-            emulator.execute(SkipIfKeyPressed { register: 0x3 });
+    #[rustfmt::skip]
+    fn should_trace_executed_instructions() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[
+            0x60, 0x05, // LD V0, 0x05
+            0x22, 0x06, // CALL 0x206
+            0x12, 0x04, // JP 0x204
+            0x70, 0x01, // ADD V0, 0x01
+            0x00, 0xEE, // RET
+        ]).unwrap();
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let collected = trace.clone();
+        emulator.set_trace(Box::new(move |pc, opcode, cpu| {
+            collected.borrow_mut().push((pc, opcode, cpu.registers[0x0]));
+        }));
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 4);
+        // When
+        for _ in 0..6 {
+            emulator.step_instruction();
         }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.input[0xA] = false;
-            emulator.cpu.registers[0x3] = 0xA;
-            let pc = emulator.cpu.program_counter;
+        // Then
+        assert_eq!(*trace.borrow(), vec![
+            (0x200, 0x6005, 0x00),
+            (0x202, 0x2206, 0x05),
+            (0x206, 0x7001, 0x05),
+            (0x208, 0x00EE, 0x06),
+            (0x204, 0x1204, 0x06),
+        ]);
+        assert!(emulator.is_halted());
+    }
 
-            // When
-            emulator.execute(SkipIfKeyPressed { register: 0x3 });
+    #[test]
+    #[rustfmt::skip]
+    fn should_count_cycles_and_instructions_of_loop() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_profiling(true);
+        emulator.load_program_from_data(&[
+            0x60, 0x00, // LD V0, 0x00
+            0x70, 0x01, // ADD V0, 0x01
+            0x30, 0x0A, // SE V0, 0x0A
+            0x12, 0x02, // JP 0x202
+            0x12, 0x08, // JP 0x208
+        ]).unwrap();
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 2);
+        // When
+        for _ in 0..35 {
+            emulator.step_instruction();
         }
+
+        // Then
+        assert_eq!(emulator.cycle_count(), 31);
+        assert!(emulator.is_halted());
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x0A);
+        let histogram = emulator.instruction_histogram();
+        assert_eq!(histogram["SetRegToConstant"], 1);
+        assert_eq!(histogram["AddConstToReg"], 10);
+        assert_eq!(histogram["SkipIfRegEqConstant"], 10);
+        assert_eq!(histogram["Jump"], 9 + 1);
     }
 
     #[test]
-    fn should_execute_skip_if_not_key_pressed() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.input[0xA] = true;
-            emulator.cpu.registers[0x3] = 0xA;
-            let pc = emulator.cpu.program_counter;
-
-            // When
-            emulator.execute(SkipIfKeyNotPressed { register: 0x3 });
+    fn should_load_program_at_custom_load_address() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_load_address(0x600).unwrap();
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 2);
-        }
+        // When
+        emulator.load_program_from_data(&[0x6A, 0x42]).unwrap();
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.input[0xA] = false;
-            emulator.cpu.registers[0x3] = 0xA;
-            let pc = emulator.cpu.program_counter;
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x600);
+        assert_eq_hex!(emulator.memory[0x600], 0x6A);
+        assert_eq_hex!(emulator.memory[0x601], 0x42);
+        assert_eq_hex!(emulator.memory[0x200], 0x00);
 
-            // When
-            emulator.execute(SkipIfKeyNotPressed { register: 0x3 });
+        // When
+        emulator.step_instruction();
+        emulator.reset();
 
-            // Then
-            assert_eq!(emulator.cpu.program_counter, pc + 4);
-        }
+        // Then
+        assert_eq_hex!(emulator.program_counter(), 0x600);
+        assert_eq_hex!(emulator.memory[0x600], 0x6A);
     }
 
     #[test]
-    fn should_execute_set_reg_to_constant() {
-        use Instruction::*;
-
+    fn should_fail_to_load_program_past_end_of_memory_from_load_address() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x4] = 0x42;
+        emulator.set_load_address(0xF00).unwrap();
 
         // When
-        emulator.execute(SetRegToConstant {
-            register: 0x4,
-            constant: 0xD7,
-        });
+        let result = emulator.load_program_from_data(&[0xAB; 0x101]);
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x4], 0xD7);
+        assert!(matches!(
+            result,
+            Err(Chip8Error::ProgramTooLarge {
+                size: 0x101,
+                capacity: 0x100
+            })
+        ));
     }
 
     #[test]
-    fn should_execute_add_const_to_reg() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x4] = 0x27;
+    fn should_reject_load_address_past_end_of_memory() {
+        // Given
+        let mut emulator = Emulator::new();
 
-            // When
-            emulator.execute(AddConstToReg {
-                register: 0x4,
-                constant: 0xD7,
-            });
+        // When
+        let result = emulator.set_load_address(0x2000);
+        emulator.reset();
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x4], 0x27 + 0xD7);
-            assert_eq_hex!(emulator.cpu.registers[0xF], 0);
-        }
+        // Then
+        assert!(matches!(
+            result,
+            Err(Chip8Error::AddressOutOfBounds(0x2000))
+        ));
+        assert_eq_hex!(emulator.load_address(), 0x200);
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+    }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x4] = 0xff;
+    #[test]
+    fn should_fail_building_emulator_with_load_address_past_end_of_memory() {
+        // When
+        let result = Emulator::builder().load_address(0x1000).build();
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(Chip8Error::AddressOutOfBounds(0x1000))
+        ));
+    }
 
+    #[test]
+    fn should_fail_building_emulator_with_invalid_memory_size() {
+        for memory_size in [0, PROGRAM_ADDRESS, XO_CHIP_MEMORY_SIZE + 1] {
             // When
-            emulator.execute(AddConstToReg {
-                register: 0x4,
-                constant: 0x01,
-            });
+            let result = Emulator::builder().memory_size(memory_size).build();
 
             // Then
-            assert_eq_hex!(emulator.cpu.registers[0x4], 0x0);
-            assert_eq_hex!(emulator.cpu.registers[0xF], 0);
+            assert!(
+                matches!(result, Err(Chip8Error::InvalidMemorySize(size)) if size == memory_size)
+            );
         }
     }
 
     #[test]
-    fn should_execute_set_reg_to_reg() {
-        use Instruction::*;
-
+    fn should_reset_load_address_past_end_of_shrunk_memory() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x3] = 0x42;
-        emulator.cpu.registers[0xa] = 0xd5;
+        emulator.set_wide_memory(true);
+        emulator.set_load_address(0x2000).unwrap();
+        emulator.load_program_from_data(&[0x6A, 0x42]).unwrap();
 
         // When
-        emulator.execute(SetRegToReg {
-            register_lhs: 0x3,
-            register_rhs: 0xa,
-        });
+        emulator.set_wide_memory(false);
+        emulator.reset_and_reload();
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x3], 0xd5);
+        assert_eq_hex!(emulator.load_address(), 0x200);
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+        assert_eq_hex!(emulator.memory[0x200], 0x6A);
     }
 
     #[test]
-    fn should_execute_bitwise_or() {
-        use Instruction::*;
-
+    fn should_reload_only_part_of_program_that_fits_after_load_address_change() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x3] = 0x42;
-        emulator.cpu.registers[0xa] = 0xd5;
+        emulator.load_program_from_data(&[0xAB; 0x200]).unwrap();
+        emulator.set_load_address(0xF00).unwrap();
 
         // When
-        emulator.execute(BitwiseOr {
-            register_lhs: 0x3,
-            register_rhs: 0xa,
-        });
+        emulator.reset_and_reload();
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 | 0xd5);
+        assert_eq_hex!(emulator.memory[0xF00], 0xAB);
+        assert_eq_hex!(emulator.memory[0xFFF], 0xAB);
     }
 
     #[test]
-    fn should_execute_bitwise_and() {
+    #[rustfmt::skip]
+    fn should_build_emulator_with_non_default_options() {
         use Instruction::*;
 
         // Given
-        let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x3] = 0x42;
-        emulator.cpu.registers[0xa] = 0xd5;
+        let mut emulator = Emulator::builder()
+            .shift_quirk(ShiftQuirk::CopyFromVy)
+            .wrap_mode(WrapMode::Wrap)
+            .cpu_clock_hz(1000)
+            .seed(7)
+            .load_address(0x600)
+            .build().unwrap();
+        emulator.load_instructions(vec![
+            BitwiseAndWithRand { register: 0x0, constant: 0xFF },
+            SetRegToConstant { register: 0x1, constant: 0x03 },
+        ]);
+        let mut seeded = Emulator::with_seed(7);
+        seeded.execute(BitwiseAndWithRand { register: 0x0, constant: 0xFF });
 
         // When
-        emulator.execute(BitwiseAnd {
-            register_lhs: 0x3,
-            register_rhs: 0xa,
-        });
+        emulator.step(Duration::from_millis(1) + Duration::from_nanos(1));
+        emulator.execute(BitwiseShrBy1 { register_lhs: 0x2, register_rhs: 0x1 });
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 & 0xd5);
+        assert_eq_hex!(emulator.program_counter(), 0x606);
+        assert_eq_hex!(emulator.cpu.registers[0x0], seeded.cpu.registers[0x0]);
+        assert_eq_hex!(emulator.cpu.registers[0x2], 0x01);
+        assert_eq_hex!(emulator.cpu.registers[0xF], 0x1);
+        assert_eq!(emulator.wrap_mode, WrapMode::Wrap);
     }
 
     #[test]
-    fn should_execute_bitwise_xor() {
-        use Instruction::*;
+    fn should_build_emulator_with_quirks_of_each_preset() {
+        let quirks = |preset| {
+            let emulator = Emulator::builder().preset(preset).build().unwrap();
+            (
+                emulator.shift_quirk,
+                emulator.jump_quirk,
+                emulator.memory_increment_quirk,
+                emulator.wrap_mode,
+            )
+        };
 
-        // Given
-        let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x3] = 0x42;
-        emulator.cpu.registers[0xa] = 0xd5;
+        assert_eq!(
+            quirks(QuirkPreset::CosmacVip),
+            (
+                ShiftQuirk::CopyFromVy,
+                JumpQuirk::V0Offset,
+                MemoryIncrementQuirk::IncrementByXPlus1,
+                WrapMode::Clip
+            )
+        );
+        assert_eq!(
+            quirks(QuirkPreset::Chip48),
+            (
+                ShiftQuirk::InPlace,
+                JumpQuirk::VxOffset,
+                MemoryIncrementQuirk::IncrementByX,
+                WrapMode::Clip
+            )
+        );
+        assert_eq!(
+            quirks(QuirkPreset::SuperChip),
+            (
+                ShiftQuirk::InPlace,
+                JumpQuirk::VxOffset,
+                MemoryIncrementQuirk::Unchanged,
+                WrapMode::Clip
+            )
+        );
+        assert_eq!(
+            quirks(QuirkPreset::XoChip),
+            (
+                ShiftQuirk::CopyFromVy,
+                JumpQuirk::V0Offset,
+                MemoryIncrementQuirk::IncrementByXPlus1,
+                WrapMode::Wrap
+            )
+        );
+    }
 
+    #[test]
+    fn should_override_preset_with_individual_quirks() {
         // When
-        emulator.execute(BitwiseXor {
-            register_lhs: 0x3,
-            register_rhs: 0xa,
-        });
+        let emulator = Emulator::builder()
+            .jump_quirk(JumpQuirk::V0Offset)
+            .preset(QuirkPreset::SuperChip)
+            .wrap_mode(WrapMode::Wrap)
+            .build()
+            .unwrap();
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 ^ 0xd5);
+        assert_eq!(emulator.quirk_preset(), Some(QuirkPreset::SuperChip));
+        assert_eq!(emulator.shift_quirk, ShiftQuirk::InPlace);
+        assert_eq!(emulator.jump_quirk, JumpQuirk::V0Offset);
+        assert_eq!(emulator.wrap_mode, WrapMode::Wrap);
+        assert_eq!(emulator.collision_quirk, CollisionQuirk::CountRows);
     }
 
     #[test]
-    fn should_execute_add_reg_to_reg() {
+    fn should_peek_instruction_without_executing() {
         use Instruction::*;
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x42;
-            emulator.cpu.registers[0xa] = 0x65;
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_instructions(vec![SetRegToConstant {
+            register: 0x4,
+            constant: 0x2A,
+        }]);
 
-            // When
-            emulator.execute(AddRegToReg {
-                register_lhs: 0x3,
-                register_rhs: 0xa,
-            });
+        // When
+        let instruction = emulator.peek_instruction();
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0x42 + 0x65);
-            assert_eq!(emulator.cpu.registers[0xF], 0);
-        }
+        // Then
+        assert_eq!(
+            instruction,
+            Some(SetRegToConstant {
+                register: 0x4,
+                constant: 0x2A
+            })
+        );
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+        assert_eq_hex!(emulator.cpu.registers[0x4], 0x00);
+    }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0xff;
-            emulator.cpu.registers[0xa] = 0x1;
+    #[test]
+    fn should_continue_after_unknown_opcode_when_ignored() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program_from_data(&[0x51, 0x21, 0x60, 0x07])
+            .unwrap();
+        emulator.set_log_level(LogLevel::Off);
+        emulator.set_unknown_opcode_policy(UnknownOpcodePolicy::Ignore);
 
-            // When
-            emulator.execute(AddRegToReg {
-                register_lhs: 0x3,
-                register_rhs: 0xa,
-            });
+        // When
+        emulator.step(Duration::from_millis(2));
+        emulator.step(Duration::from_millis(2));
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0x0);
-            assert_eq!(emulator.cpu.registers[0xF], 1);
-        }
+        // Then
+        assert!(!emulator.is_halted());
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x07);
+        assert_eq_hex!(emulator.program_counter(), 0x204);
     }
 
     #[test]
-    fn should_execute_sub_reg2_from_reg1() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x65;
-            emulator.cpu.registers[0x4] = 0x42;
+    fn should_continue_after_unknown_opcode_when_logged() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program_from_data(&[0x51, 0x21, 0x60, 0x07])
+            .unwrap();
+        emulator.set_log_level(LogLevel::Off);
+        emulator.set_unknown_opcode_policy(UnknownOpcodePolicy::Log);
 
-            // When
-            emulator.execute(SubReg2FromReg1 {
-                register_lhs: 0x3,
-                register_rhs: 0x4,
-            });
+        // When
+        emulator.step(Duration::from_millis(2));
+        emulator.step(Duration::from_millis(2));
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0x65 - 0x42);
-            assert_eq!(emulator.cpu.registers[0xF], 1);
-        }
+        // Then
+        assert!(!emulator.is_halted());
+        assert!(emulator.last_error().is_none());
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x07);
+    }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x0;
-            emulator.cpu.registers[0x4] = 0x4;
+    #[test]
+    fn should_halt_on_unknown_opcode_when_halting() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program_from_data(&[0x51, 0x21, 0x60, 0x07])
+            .unwrap();
+        emulator.set_log_level(LogLevel::Off);
+        emulator.set_unknown_opcode_policy(UnknownOpcodePolicy::Halt);
 
-            // When
-            emulator.execute(SubReg2FromReg1 {
-                register_lhs: 0x3,
-                register_rhs: 0x4,
-            });
+        // When
+        emulator.step(Duration::from_millis(2));
+        emulator.step(Duration::from_millis(2));
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0xfc);
-            assert_eq!(emulator.cpu.registers[0xF], 0);
-        }
+        // Then
+        assert!(emulator.is_halted());
+        assert!(matches!(
+            emulator.last_error(),
+            Some(Chip8Error::UnknownOpcode(0x5121))
+        ));
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x00);
+        assert_eq_hex!(emulator.program_counter(), 0x202);
     }
 
     #[test]
-    fn should_execute_sub_reg1_from_reg2() {
-        use Instruction::*;
+    #[should_panic(expected = "unknown instruction 0x5121 at 0x0200")]
+    fn should_panic_on_unknown_opcode_when_panicking() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator
+            .load_program_from_data(&[0x51, 0x21, 0x60, 0x07])
+            .unwrap();
+        emulator.set_log_level(LogLevel::Off);
+        emulator.set_unknown_opcode_policy(UnknownOpcodePolicy::Panic);
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x42;
-            emulator.cpu.registers[0x4] = 0x65;
+        // When
+        emulator.step(Duration::from_millis(2));
+    }
 
-            // When
-            emulator.execute(SubReg1FromReg2 {
-                register_lhs: 0x3,
-                register_rhs: 0x4,
-            });
+    #[test]
+    fn should_tick_timers_without_executing_instructions() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[0x60, 0x05]).unwrap();
+        emulator.cpu.delay_timer = 10;
+        emulator.cpu.sound_timer = 2;
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0x65 - 0x42);
-            assert_eq!(emulator.cpu.registers[0xF], 1);
-        }
+        // When
+        emulator.tick_timers(TIMER_INTERVAL * 3);
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0x7;
-            emulator.cpu.registers[0x4] = 0x0;
+        // Then
+        assert_eq!(emulator.cpu.delay_timer, 7);
+        assert_eq!(emulator.cpu.sound_timer, 0);
+        assert_eq_hex!(emulator.program_counter(), 0x200);
+        assert_eq_hex!(emulator.cpu.registers[0x0], 0x00);
 
-            // When
-            emulator.execute(SubReg1FromReg2 {
-                register_lhs: 0x3,
-                register_rhs: 0x4,
-            });
+        // When
+        emulator.step_instruction();
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0xf9);
-            assert_eq!(emulator.cpu.registers[0xF], 0);
-        }
+        // Then
+        assert_eq!(emulator.cpu.delay_timer, 7);
+        assert_eq_hex!(emulator.program_counter(), 0x202);
     }
 
     #[test]
-    fn should_execute_bitwise_shr_by_1() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0b11001101;
-
-            // When
-            emulator.execute(BitwiseShrBy1 { register: 0x3 });
+    fn should_beep_while_sound_timer_runs() {
+        // Given
+        let mut emulator = Emulator::new();
+        assert!(!emulator.is_beeping());
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0b1100110);
-            assert_eq!(emulator.cpu.registers[0xF], 1);
-        }
+        // When
+        emulator.cpu.sound_timer = 1;
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0b10001110;
+        // Then
+        assert!(emulator.is_beeping());
 
-            // When
-            emulator.execute(BitwiseShrBy1 { register: 0x3 });
+        // When
+        emulator.tick_timers(TIMER_INTERVAL);
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0b1000111);
-            assert_eq!(emulator.cpu.registers[0xF], 0);
-        }
+        // Then
+        assert!(!emulator.is_beeping());
     }
 
     #[test]
-    fn should_execute_bitwise_shl_by_1() {
+    fn should_build_emulator_with_64kb_memory() {
         use Instruction::*;
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0b1101;
+        // Given
+        let mut emulator = Emulator::builder()
+            .memory_size(XO_CHIP_MEMORY_SIZE)
+            .build()
+            .unwrap();
+        emulator.cpu.register_i = 0x2000;
+        emulator.cpu.registers[0x0] = 0x42;
 
-            // When
-            emulator.execute(BitwiseShlBy1 { register: 0x3 });
+        // When
+        emulator.execute(StoreRegisters { last_register: 0 });
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0b11010);
-            assert_eq!(emulator.cpu.registers[0xF], 0);
-        }
+        // Then
+        assert_eq!(emulator.memory.len(), XO_CHIP_MEMORY_SIZE);
+        assert_eq_hex!(emulator.memory[0x2000], 0x42);
+        assert_eq!(emulator.memory[..FONT_SPRITES.len()], FONT_SPRITES);
+    }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.registers[0x3] = 0b11001110;
+    #[test]
+    fn should_build_emulator_with_small_memory() {
+        // Given
+        let mut emulator = Emulator::builder().memory_size(0x204).build().unwrap();
 
-            // When
-            emulator.execute(BitwiseShlBy1 { register: 0x3 });
+        // When
+        let result = emulator.load_program_from_data(&[0x60, 0x05, 0x61, 0x07, 0x62, 0x09]);
 
-            // Then
-            assert_eq_hex!(emulator.cpu.registers[0x3], 0b10011100);
-            assert_eq!(emulator.cpu.registers[0xF], 1);
-        }
+        // Then
+        assert!(matches!(
+            result,
+            Err(Chip8Error::ProgramTooLarge {
+                size: 6,
+                capacity: 4
+            })
+        ));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_run_exact_number_of_instructions() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[
+            0x60, 0x00, // LD V0, 0x00
+            0x70, 0x01, // ADD V0, 0x01
+            0x12, 0x02, // JP 0x202
+        ]).unwrap();
+
+        // When
+        let executed = emulator.run_instructions(100);
+
+        // Then
+        assert_eq!(executed, 100);
+        assert_eq_hex!(emulator.cpu.registers[0x0], 50);
+        assert_eq_hex!(emulator.program_counter(), 0x204);
     }
 
     #[test]
-    fn should_execute_set_address() {
-        use Instruction::*;
-
+    #[rustfmt::skip]
+    fn should_run_instructions_until_breakpoint_or_halt() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.register_i = 0x0;
+        emulator.load_program_from_data(&[
+            0x60, 0x05, // LD V0, 0x05
+            0x61, 0x07, // LD V1, 0x07
+            0x12, 0x04, // JP 0x204
+        ]).unwrap();
+        emulator.add_breakpoint(0x202);
 
         // When
-        emulator.execute(SetAddress { address: 0x456 });
+        let before_breakpoint = emulator.run_instructions(10);
+        let after_breakpoint = emulator.run_instructions(10);
 
         // Then
-        assert_eq_hex!(emulator.cpu.register_i, 0x456);
+        assert_eq!(before_breakpoint, 1);
+        assert_eq!(after_breakpoint, 2);
+        assert!(emulator.is_halted());
+        assert_eq_hex!(emulator.cpu.registers[0x1], 0x07);
     }
 
     #[test]
-    fn should_execute_jump_with_v0_offset() {
-        use Instruction::*;
-
+    fn should_list_active_pixels_in_row_major_order() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0] = 0xff;
+        emulator
+            .active_pixels
+            .extend([(63, 31), (5, 2), (0, 2), (40, 0), (7, 31)]);
 
         // When
-        emulator.execute(JumpWithV0Offset { address: 0x456 });
+        let pixels = emulator.active_pixels_sorted();
 
         // Then
-        assert_eq_hex!(
-            emulator.cpu.program_counter,
-            0x456 + emulator.cpu.registers[0] as u16
-        );
+        assert_eq!(pixels, vec![(40, 0), (0, 2), (5, 2), (7, 31), (63, 31)]);
     }
 
     #[test]
-    fn should_execute_display_sprite_no_xor() {
-        use Instruction::*;
+    fn should_index_palette_by_plane_bits() {
+        // Given
+        let first_plane = Pixels::from_iter([(0, 0), (2, 0)]);
+        let second_plane = Pixels::from_iter([(1, 0), (2, 0)]);
+
+        // Then
+        assert_eq!(palette_index(&first_plane, &second_plane, (3, 0)), 0);
+        assert_eq!(palette_index(&first_plane, &second_plane, (0, 0)), 1);
+        assert_eq!(palette_index(&first_plane, &second_plane, (1, 0)), 2);
+        assert_eq!(palette_index(&first_plane, &second_plane, (2, 0)), 3);
+    }
 
+    #[test]
+    fn should_ignore_pixels_outside_display() {
         // Given
-        let mut emulator = Emulator::new();
-        emulator.cpu.registers[2] = 20;
-        emulator.cpu.registers[3] = 10;
-        emulator.cpu.register_i = 0x600;
-        emulator.memory[0x600] = 0b10101010;
-        emulator.memory[0x601] = 0b00111010;
+        let mut pixels = Pixels::new();
 
         // When
-        emulator.execute(DisplaySprite {
-            register_x: 2,
-            register_y: 3,
-            n_bytes: 2,
-        });
+        let inserted = pixels.insert((200, 200));
+        pixels.extend([(64, 0), (0, 32), (3, 4)]);
 
         // Then
-        assert_eq!(emulator.active_pixels.len(), 8);
-        assert!(emulator.active_pixels.contains(&(20, 10)));
-        assert!(emulator.active_pixels.contains(&(22, 10)));
-        assert!(emulator.active_pixels.contains(&(24, 10)));
-        assert!(emulator.active_pixels.contains(&(26, 10)));
-        assert!(emulator.active_pixels.contains(&(22, 11)));
-        assert!(emulator.active_pixels.contains(&(23, 11)));
-        assert!(emulator.active_pixels.contains(&(24, 11)));
-        assert!(emulator.active_pixels.contains(&(26, 11)));
-        assert_eq!(emulator.cpu.registers[0xF], 0);
+        assert!(!inserted);
+        assert!(!pixels.contains(&(200, 200)));
+        assert_eq!(pixels.iter().collect::<Vec<_>>(), vec![(3, 4)]);
     }
 
     #[test]
-    fn should_execute_display_sprite_xor() {
+    fn should_set_address_of_font_char() {
         use Instruction::*;
 
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[2] = 20;
-        emulator.cpu.registers[3] = 10;
-        emulator.cpu.register_i = 0x600;
-        emulator.memory[0x600] = 0b10101010;
-        emulator.active_pixels.insert((22, 10));
-        emulator.active_pixels.insert((26, 10));
 
-        // When
-        emulator.execute(DisplaySprite {
-            register_x: 2,
-            register_y: 3,
-            n_bytes: 1,
-        });
+        #[rustfmt::skip]
+        let cases = [
+            (0x0, 0x00),
+            (0x1, 0x05),
+            (0x9, 0x2D),
+            (0xA, 0x32),
+            (0xF, 0x4B),
+            (0x1A, 0x32),
+            (0xFF, 0x4B),
+        ];
+        for (character, address) in cases {
+            // When
+            emulator.cpu.registers[0x3] = character;
+            emulator.execute(SetAddressOfFontChar { register: 0x3 });
 
-        // Then
-        assert_eq!(emulator.active_pixels.len(), 2);
-        assert!(emulator.active_pixels.contains(&(20, 10)));
-        assert!(emulator.active_pixels.contains(&(24, 10)));
-        assert_eq!(emulator.cpu.registers[0xF], 1);
+            // Then
+            assert_eq_hex!(emulator.cpu.register_i, address);
+        }
     }
 
     #[test]
-    fn should_execute_display_sprite_near_edge() {
+    fn should_draw_glyph_of_custom_font() {
         use Instruction::*;
 
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[2] = (SCREEN_WIDTH - 3) as u8;
-        emulator.cpu.registers[3] = (SCREEN_HEIGHT - 1) as u8;
-        emulator.cpu.register_i = 0x600;
-        emulator.memory[0x600] = 0b10101010;
-        emulator.memory[0x601] = 0b01101011;
+        let mut font = [0; 80];
+        font[0xB * 5..0xB * 5 + 5].copy_from_slice(&[0x80, 0x40, 0x20, 0x10, 0x08]);
+        emulator.set_font(&font);
+        emulator.reset();
+        emulator.cpu.registers[0x0] = 0xB;
 
         // When
+        emulator.execute(SetAddressOfFontChar { register: 0x0 });
         emulator.execute(DisplaySprite {
-            register_x: 2,
-            register_y: 3,
-            n_bytes: 2,
+            register_x: 0x1,
+            register_y: 0x1,
+            n_bytes: 5,
         });
 
         // Then
-        assert_eq!(emulator.active_pixels.len(), 2);
-        assert!(emulator
-            .active_pixels
-            .contains(&(SCREEN_WIDTH - 3, SCREEN_HEIGHT - 1)));
-        assert!(emulator
-            .active_pixels
-            .contains(&(SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1)));
-        assert_eq!(emulator.cpu.registers[0xF], 0);
+        assert_eq_hex!(emulator.cpu.register_i, 0x37);
+        assert_eq!(
+            emulator.active_pixels,
+            Pixels::from_iter([(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)])
+        );
     }
 
     #[test]
-    fn should_execute_display_sprite_wrap() {
+    fn should_render_display_as_ascii() {
         use Instruction::*;
 
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[2] = (7 * SCREEN_WIDTH + 5) as u8;
-        emulator.cpu.registers[3] = (2 * SCREEN_HEIGHT + 10) as u8;
-        emulator.cpu.register_i = 0x600;
-        emulator.memory[0x600] = 0b10000010;
-        emulator.memory[0x601] = 0b01001001;
+        emulator.cpu.registers[0x0] = 0x1;
+        emulator.cpu.registers[0x1] = 2;
+        emulator.cpu.registers[0x2] = 1;
 
         // When
+        emulator.execute(SetAddressOfFontChar { register: 0x0 });
         emulator.execute(DisplaySprite {
-            register_x: 2,
-            register_y: 3,
-            n_bytes: 2,
+            register_x: 0x1,
+            register_y: 0x2,
+            n_bytes: 5,
         });
+        let ascii = emulator.display_to_ascii();
 
         // Then
-        assert_eq!(emulator.active_pixels.len(), 5);
-        assert!(emulator.active_pixels.contains(&(5, 10)));
-        assert!(emulator.active_pixels.contains(&(11, 10)));
-        assert!(emulator.active_pixels.contains(&(6, 11)));
-        assert!(emulator.active_pixels.contains(&(9, 11)));
-        assert!(emulator.active_pixels.contains(&(12, 11)));
-        assert_eq!(emulator.cpu.registers[0xF], 0);
+        let lines: Vec<&str> = ascii.lines().collect();
+        assert_eq!(lines.len(), SCREEN_HEIGHT as usize);
+        assert!(lines.iter().all(|line| line.len() == SCREEN_WIDTH as usize));
+        let glyph: Vec<&str> = lines[..7].iter().map(|line| line.trim_end()).collect();
+        assert_eq!(
+            glyph.join("\n"),
+            ["", "    #", "   ##", "    #", "    #", "   ###", ""].join("\n")
+        );
+        assert!(lines[7..].iter().all(|line| line.trim().is_empty()));
+
+        // When
+        emulator.execute(HighResolution);
+
+        // Then
+        let ascii = emulator.display_to_ascii();
+        assert_eq!(ascii.lines().count(), HIRES_SCREEN_HEIGHT as usize);
+        assert!(ascii
+            .lines()
+            .all(|line| line.len() == HIRES_SCREEN_WIDTH as usize));
     }
 
     #[test]
-    fn should_execute_set_reg_to_delay_timer() {
-        use Instruction::*;
+    #[rustfmt::skip]
+    fn should_fail_step_with_error_that_halted_execution() {
+        for (program, expected_error) in [
+            ([0x22, 0x00], "stack overflow"),             // CALL 0x200
+            ([0x00, 0xEE], "return with an empty stack"), // RET
+            ([0x51, 0x21], "unknown instruction 0x5121"),
+        ] {
+            // Given
+            let mut emulator = Emulator::new();
+            emulator.set_log_level(LogLevel::Off);
+            emulator.set_unknown_opcode_policy(UnknownOpcodePolicy::Halt);
+            emulator.load_program_from_data(&program).unwrap();
+
+            // When
+            let mut result = Ok(StepStatus::Running);
+            for _ in 0..20 {
+                result = emulator.try_step(Duration::from_millis(2)).map_err(|e| e.to_string());
+                if result.is_err() {
+                    break;
+                }
+            }
 
+            // Then
+            assert_eq!(result, Err(expected_error.to_string()));
+            assert!(emulator.try_step(Duration::from_millis(2)).is_ok());
+        }
+    }
+
+    #[test]
+    fn should_fail_step_on_fetch_past_end_of_memory() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.delay_timer = 42;
+        emulator.set_log_level(LogLevel::Off);
+        emulator.cpu.program_counter = 0xFFF;
 
         // When
-        emulator.execute(SetRegToDelayTimer { register: 0x3 });
+        let result = emulator.try_step(Duration::from_millis(2));
+        emulator.reset();
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x3], 42);
+        assert!(matches!(result, Err(Chip8Error::AddressOutOfBounds(0xFFF))));
     }
 
     #[test]
-    fn should_execute_await_and_set_key_press() {
-        use Instruction::*;
-
+    fn should_clone_errors_keeping_their_message() {
         // Given
-        let mut emulator = Emulator::new();
-        emulator.input[0xC] = true;
+        let io_error = Chip8Error::Io(io::Error::new(io::ErrorKind::NotFound, "no rom"));
+        let save_state_error = SaveState::from_bytes(&[0xFF]).unwrap_err();
 
         // When
-        emulator.execute(AwaitAndSetKeyPress { register: 0x3 });
+        let io_clone = io_error.clone();
+        let save_state_clone = save_state_error.clone();
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x3], 0xC);
+        assert!(matches!(&io_clone, Chip8Error::Io(e) if e.kind() == io::ErrorKind::NotFound));
+        assert_eq!(io_clone.to_string(), io_error.to_string());
+        assert!(matches!(save_state_clone, Chip8Error::InvalidSaveState(_)));
+        assert_eq!(save_state_clone.to_string(), save_state_error.to_string());
     }
 
     #[test]
-    fn should_execute_await_and_set_key_press_with_no_delay() {
-        use Instruction::*;
-
+    #[rustfmt::skip]
+    fn should_disassemble_window_around_program_counter() {
         // Given
         let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[
+            0x60, 0x05, // LD V0, 0x05
+            0x61, 0x07, // LD V1, 0x07
+            0x80, 0x14, // ADD V0, V1
+            0x12, 0x06, // JP 0x206
+        ]).unwrap();
+        emulator.run_instructions(2);
 
         // When
-        emulator.input[0xC] = false;
-        emulator.load_instructions(vec![AwaitAndSetKeyPress { register: 0x3 }]);
-        emulator.step(Duration::from_nanos(1));
-        emulator.input[0xC] = true;
-        emulator.step(Duration::from_nanos(1));
+        let window = emulator.disassemble_window(emulator.program_counter(), 1);
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x3], 0xC);
+        assert_eq!(window, vec![
+            (0x202, "  LD V1, 0x07".to_string()),
+            (0x204, "> ADD V0, V1".to_string()),
+            (0x206, "  JP 0x206".to_string()),
+        ]);
     }
 
     #[test]
-    fn should_execute_await_and_set_delay_timer() {
-        use Instruction::*;
-
+    fn should_clamp_disassemble_window_to_memory() {
         // Given
-        let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x3] = 0x7d;
+        let emulator = Emulator::new();
 
         // When
-        emulator.execute(SetDelayTimer { register: 0x3 });
+        let start = emulator.disassemble_window(0x000, 2);
+        let end = emulator.disassemble_window(0xFFE, 2);
 
         // Then
-        assert_eq!(emulator.cpu.delay_timer, 0x7d);
+        let addresses = |window: Vec<(u16, String)>| -> Vec<u16> {
+            window.into_iter().map(|(address, _)| address).collect()
+        };
+        assert_eq!(addresses(start), vec![0x000, 0x002, 0x004]);
+        assert_eq!(addresses(end), vec![0xFFA, 0xFFC, 0xFFE]);
     }
 
     #[test]
-    fn should_execute_await_and_set_sound_timer() {
-        use Instruction::*;
-
+    fn should_beep_for_full_sound_timer_duration_with_small_steps() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x3] = 0x7d;
+        emulator.load_program_from_data(&[0x12, 0x00]).unwrap();
+        emulator.cpu.sound_timer = 4;
 
         // When
-        emulator.execute(SetSoundTimer { register: 0x3 });
+        let mut beeping_time = Duration::ZERO;
+        while emulator.is_beeping() {
+            emulator.step(Duration::from_millis(1));
+            beeping_time += Duration::from_millis(1);
+        }
 
         // Then
-        assert_eq!(emulator.cpu.sound_timer, 0x7d);
+        assert_eq!(beeping_time, Duration::from_millis(67));
     }
 
     #[test]
-    fn should_execute_add_reg_to_address_without_carry() {
-        use Instruction::*;
-
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.register_i = 0xd79;
-            emulator.cpu.registers[0x3] = 0x7d;
-
-            // When
-            emulator.execute(AddRegToAddressWithoutCarry { register: 0x3 });
+    fn should_beep_for_full_sound_timer_duration_with_irregular_steps() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[0x12, 0x00]).unwrap();
+        emulator.cpu.sound_timer = 4;
 
-            // Then
-            assert_eq_hex!(emulator.cpu.register_i, 0xd79 + 0x7d);
-            assert_eq!(emulator.cpu.registers[0xF], 0);
+        // When
+        for elapsed_ms in [3, 25, 1, 7, 30] {
+            emulator.step(Duration::from_millis(elapsed_ms));
         }
 
-        {
-            // Given
-            let mut emulator = Emulator::new();
-            emulator.cpu.register_i = 0xf79;
-            emulator.cpu.registers[0x3] = 0x7d;
+        // Then
+        assert!(emulator.is_beeping());
+        assert_eq!(emulator.cpu.sound_timer, 1);
 
-            // When
-            emulator.execute(AddRegToAddressWithoutCarry { register: 0x3 });
+        // When
+        emulator.step(Duration::from_millis(1));
 
-            // Then
-            assert_eq_hex!(emulator.cpu.register_i, 0xf79 + 0x7d);
-            assert_eq!(emulator.cpu.registers[0xF], 0);
-        }
+        // Then
+        assert!(!emulator.is_beeping());
     }
 
     #[test]
-    fn should_execute_store_reg_bcd() {
-        use Instruction::*;
-
+    fn should_dump_state_as_json() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x3] = 196;
-        emulator.cpu.register_i = 0x765;
+        #[rustfmt::skip]
+        emulator.load_program_from_data(&[
+            0x60, 0x0A, // LD V0, 0x0A
+            0xA0, 0x00, // LD I, 0x000
+            0x22, 0x08, // CALL 0x208
+            0x12, 0x06, // JP 0x206
+            0xD1, 0x11, // DRW V1, V1, 1
+        ]).unwrap();
 
         // When
-        emulator.execute(StoreRegBcd { register: 0x3 });
+        emulator.run_instructions(4);
+        let json: serde_json::Value = serde_json::from_str(&emulator.to_json()).unwrap();
 
         // Then
-        assert_eq!(emulator.memory[emulator.cpu.register_i as usize + 0], 1);
-        assert_eq!(emulator.memory[emulator.cpu.register_i as usize + 1], 9);
-        assert_eq!(emulator.memory[emulator.cpu.register_i as usize + 2], 6);
+        assert_eq!(json["registers"][0], 0x0A);
+        assert_eq!(json["register_i"], FONT_ADDRESS);
+        assert_eq!(json["program_counter"], 0x20A);
+        assert_eq!(json["stack"][0], 0x206);
+        assert_eq!(json["stack_index"], 0);
+        assert_eq!(json["delay_timer"], 0);
+        assert_eq!(json["sound_timer"], 0);
+        assert_eq!(json["resolution"], "Low");
+        assert_eq!(json["display"].as_array().unwrap().len(), 32);
+        assert_eq!(json["display"][0], "F000000000000000");
+        assert_eq!(json["display"][1], "0000000000000000");
     }
 
     #[test]
-    fn should_execute_store_registers() {
-        use Instruction::*;
+    fn should_step_back_to_state_before_last_instructions() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_rewind_depth(8);
+        #[rustfmt::skip]
+        emulator.load_program_from_data(&[
+            0x60, 0x01, // LD V0, 0x01
+            0x70, 0x02, // ADD V0, 0x02
+            0xA3, 0x00, // LD I, 0x300
+        ]).unwrap();
+        emulator.run_instructions(3);
+
+        // When
+        let stepped_back_once = emulator.step_back();
+        let cpu_after_one_step_back = emulator.cpu_snapshot();
+        let stepped_back_twice = emulator.step_back();
+
+        // Then
+        assert!(stepped_back_once && stepped_back_twice);
+        assert_eq!(cpu_after_one_step_back.registers[0], 3);
+        assert_eq_hex!(cpu_after_one_step_back.register_i, 0);
+        assert_eq_hex!(cpu_after_one_step_back.program_counter, 0x204);
+        assert_eq!(emulator.cpu.registers[0], 1);
+        assert_eq_hex!(emulator.cpu.program_counter, 0x202);
+        assert_eq!(emulator.cycle_count(), 1);
+    }
 
+    #[test]
+    #[rustfmt::skip]
+    fn should_step_back_over_plane_and_audio_instructions() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x0] = 0x41;
-        emulator.cpu.registers[0x1] = 0xb7;
-        emulator.cpu.registers[0x2] = 0x09;
-        emulator.cpu.registers[0x3] = 0xff;
-        emulator.cpu.register_i = 0x765;
+        emulator.set_rewind_depth(8);
+        emulator.load_program_from_data(&[
+            0xF2, 0x01, // PLANE 2
+            0x60, 0x70, // LD V0, 0x70
+            0xF0, 0x3A, // PITCH V0
+            0xA2, 0x00, // LD I, 0x200
+            0xF0, 0x02, // AUDIO
+        ]).unwrap();
+        emulator.run_instructions(5);
+        assert!(emulator.sound_pattern().is_some());
 
         // When
-        emulator.execute(StoreRegisters { last_register: 0x2 });
+        emulator.step_back();
 
         // Then
-        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 0], 0x41);
-        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 1], 0xb7);
-        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 2], 0x09);
-        assert_eq_hex!(emulator.memory[emulator.cpu.register_i as usize + 3], 0);
+        assert_eq!(emulator.sound_pattern(), None);
+        assert_eq!(emulator.pitch, 0x70);
+
+        // When
+        emulator.step_back();
+        emulator.step_back();
+
+        // Then
+        assert_eq!(emulator.pitch, DEFAULT_PITCH);
+        assert_eq!(emulator.selected_planes(), 0b10);
+
+        // When
+        emulator.step_back();
+        emulator.step_back();
+
+        // Then
+        assert_eq!(emulator.selected_planes(), 0b01);
+        assert_eq_hex!(emulator.program_counter(), 0x200);
     }
 
     #[test]
-    fn should_execute_load_registers() {
-        use Instruction::*;
+    fn should_only_step_back_as_far_as_rewind_depth() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.set_rewind_depth(2);
+        emulator
+            .load_program_from_data(&[0x70, 0x01, 0x70, 0x01, 0x70, 0x01])
+            .unwrap();
+        emulator.run_instructions(3);
+
+        // When
+        let stepped_back = [
+            emulator.step_back(),
+            emulator.step_back(),
+            emulator.step_back(),
+        ];
+
+        // Then
+        assert_eq!(stepped_back, [true, true, false]);
+        assert_eq!(emulator.cpu.registers[0], 1);
+    }
 
+    #[test]
+    fn should_not_record_rewind_states_by_default() {
         // Given
         let mut emulator = Emulator::new();
-        emulator.cpu.registers[0x0] = 0xff;
-        emulator.cpu.registers[0x1] = 0xff;
-        emulator.cpu.registers[0x2] = 0xff;
-        emulator.cpu.registers[0x3] = 0xff;
-        emulator.cpu.register_i = 0x765;
-        emulator.memory[emulator.cpu.register_i as usize + 0] = 0x71;
-        emulator.memory[emulator.cpu.register_i as usize + 1] = 0xa5;
-        emulator.memory[emulator.cpu.register_i as usize + 2] = 0x06;
-        emulator.memory[emulator.cpu.register_i as usize + 3] = 0x51;
+        emulator.load_program_from_data(&[0x70, 0x01]).unwrap();
+        emulator.run_instructions(1);
 
         // When
-        emulator.execute(LoadRegisters { last_register: 0x2 });
+        let stepped_back = emulator.step_back();
 
         // Then
-        assert_eq_hex!(emulator.cpu.registers[0x0], 0x71);
-        assert_eq_hex!(emulator.cpu.registers[0x1], 0xa5);
-        assert_eq_hex!(emulator.cpu.registers[0x2], 0x06);
-        assert_eq_hex!(emulator.cpu.registers[0x3], 0xff);
+        assert!(!stepped_back);
+        assert_eq!(emulator.cpu.registers[0], 1);
     }
 }
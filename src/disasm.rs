@@ -0,0 +1,98 @@
+use crate::chip8::{Instruction, PROGRAM_ADDRESS};
+
+/// Disassembles a ROM loaded at the program address, one `(address, mnemonic)` pair
+/// per opcode. A trailing odd byte is rendered as data, padded with the zero that
+/// follows it in memory. The address word of `F000 NNNN` is rendered as data too.
+pub fn disassemble(data: &[u8]) -> Vec<(u16, String)> {
+    disassemble_at(data, PROGRAM_ADDRESS as u16)
+}
+
+/// Like `disassemble`, for `data` found at `address` in memory.
+pub fn disassemble_at(data: &[u8], address: u16) -> Vec<(u16, String)> {
+    let mut listing = Vec::new();
+    let mut after_long_address = false;
+    for (i, chunk) in data.chunks(2).enumerate() {
+        let address = address.wrapping_add(2 * i as u16);
+        let word = match *chunk {
+            [high, low] => u16::from_be_bytes([high, low]),
+            [high] => u16::from_be_bytes([high, 0]),
+            _ => unreachable!(),
+        };
+        let instruction = Instruction::decode(word);
+        let line = if chunk.len() == 1 || after_long_address {
+            format!("DW {:#06X}", word)
+        } else {
+            instruction.to_string()
+        };
+        after_long_address = !after_long_address && instruction == Instruction::SetLongAddress;
+        listing.push((address, line));
+    }
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_disassemble_font_display_rom() {
+        // Given
+        let data = vec![
+            0x00, 0xE0,
+            0x60, 0x0F,
+            0xF0, 0x29,
+            0xD2, 0x2A,
+            0xA2, 0x2A,
+        ];
+
+        // When
+        let listing = disassemble(&data);
+
+        // Then
+        assert_eq!(listing, vec![
+            (0x200, "CLS".to_string()),
+            (0x202, "LD V0, 0x0F".to_string()),
+            (0x204, "LD F, V0".to_string()),
+            (0x206, "DRW V2, V2, 0xA".to_string()),
+            (0x208, "LD I, 0x22A".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn should_disassemble_unknown_opcode_and_trailing_byte_as_data() {
+        // Given
+        let data = vec![0xFF, 0xFF, 0xAB];
+
+        // When
+        let listing = disassemble(&data);
+
+        // Then
+        assert_eq!(
+            listing,
+            vec![
+                (0x200, "DW 0xFFFF".to_string()),
+                (0x202, "DW 0xAB00".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_disassemble_long_address_word_as_data() {
+        // Given
+        let data = vec![0xF0, 0x00, 0xF0, 0x00, 0x00, 0xE0];
+
+        // When
+        let listing = disassemble(&data);
+
+        // Then
+        assert_eq!(
+            listing,
+            vec![
+                (0x200, "LD I, LONG".to_string()),
+                (0x202, "DW 0xF000".to_string()),
+                (0x204, "CLS".to_string()),
+            ]
+        );
+    }
+}
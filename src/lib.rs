@@ -1,2 +1,9 @@
-pub mod chip8;
-pub mod sdl_platform;
\ No newline at end of file
+pub mod asm;
+pub mod chip8;
+pub mod disasm;
+pub mod platform;
+pub mod remote_input;
+#[cfg(feature = "sdl")]
+pub mod sdl_platform;
+
+pub use chip8::Instruction;
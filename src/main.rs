@@ -1,21 +1,114 @@
-mod chip8;
-mod sdl_platform;
+#[cfg(unix)]
+use chip8_emulator::remote_input;
+use chip8_emulator::{chip8, sdl_platform};
 
-use std::{env, process::exit};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::exit,
+};
 
 pub fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!(
-            "Wrong number of arguments: expected {}, given {}",
-            1,
-            args.len() - 1
-        );
-        exit(1);
+    let mut rom_path: Option<String> = None;
+    let mut profile = false;
+    let mut verbose = false;
+    let mut input_socket: Option<String> = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => profile = true,
+            "--verbose" => verbose = true,
+            "--input-socket" => match args.next() {
+                Some(path) => input_socket = Some(path),
+                None => {
+                    eprintln!("Missing path after --input-socket");
+                    exit(1);
+                }
+            },
+            _ if rom_path.is_none() => rom_path = Some(arg),
+            _ => {
+                eprintln!("Unexpected argument: {}", arg);
+                exit(1);
+            }
+        }
     }
 
+    let rom_path = match rom_path {
+        Some(rom_path) => rom_path,
+        None => {
+            eprintln!("Usage: chip8-emulator [--profile] [--verbose] [--input-socket <path>] <filepath-to-rom-or-directory>");
+            exit(1);
+        }
+    };
+
     let mut emulator = chip8::Emulator::new();
-    emulator.load_program_from_file(&args[1]);
+    emulator.set_profiling(profile);
+    if verbose {
+        emulator.set_log_level(chip8::LogLevel::Debug);
+    }
+    let playlist = if Path::new(&rom_path).is_dir() {
+        match read_playlist(Path::new(&rom_path)) {
+            Ok(playlist) if !playlist.is_empty() => Some(playlist),
+            Ok(_) => {
+                eprintln!("No ROMs found in {}", rom_path);
+                exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", rom_path, e);
+                exit(1);
+            }
+        }
+    } else {
+        if let Err(e) = emulator.load_program_from_file(&rom_path) {
+            eprintln!("Failed to load {}: {}", rom_path, e);
+            exit(1);
+        }
+        None
+    };
     let mut platform = sdl_platform::SDLPlatform::new();
-    platform.run(&mut emulator);
+    if let Some(path) = input_socket {
+        attach_input_socket(&mut platform, &path);
+    }
+    match playlist {
+        Some(playlist) => platform.run_playlist(&mut emulator, playlist),
+        None => platform.run(&mut emulator),
+    }
+
+    if profile {
+        println!("{} instructions executed", emulator.cycle_count());
+        print!(
+            "{}",
+            chip8::format_instruction_histogram(emulator.instruction_histogram())
+        );
+    }
+}
+
+/// The files in `directory`, sorted by name.
+fn read_playlist(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut playlist = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_file() {
+            playlist.push(path);
+        }
+    }
+    playlist.sort();
+    Ok(playlist)
+}
+
+#[cfg(unix)]
+fn attach_input_socket(platform: &mut sdl_platform::SDLPlatform, path: &str) {
+    match remote_input::RemoteKeypad::bind(path) {
+        Ok(remote_keypad) => platform.set_remote_keypad(remote_keypad),
+        Err(e) => {
+            eprintln!("Failed to listen on input socket {}: {}", path, e);
+            exit(1);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn attach_input_socket(_platform: &mut sdl_platform::SDLPlatform, _path: &str) {
+    eprintln!("--input-socket is only supported on Unix platforms");
+    exit(1);
 }
@@ -0,0 +1,215 @@
+use std::io::{self, BufRead};
+use std::time::Duration;
+
+use crate::chip8::{Chip8Error, Emulator, Pixels};
+
+/// Frontend the emulator runs on: supplies the keypad and presents the display and sound.
+pub trait Platform {
+    fn poll_input(&mut self) -> [bool; 16];
+    fn present(&mut self, active_pixels: &Pixels);
+    /// Presents both XO-CHIP planes. Frontends without a palette show the first one.
+    fn present_planes(&mut self, first_plane: &Pixels, _second_plane: &Pixels) {
+        self.present(first_plane);
+    }
+    fn beep(&mut self, on: bool);
+    fn should_close(&self) -> bool;
+}
+
+const FRAME_TIME: Duration = Duration::from_millis(16);
+const STEP_TIME: Duration = Duration::from_millis(1);
+
+/// Runs the emulator without a window. Time advances by a fixed amount per frame,
+/// so the same program and input always produce the same frames.
+pub struct HeadlessPlatform {
+    input: [bool; 16],
+    recording: Option<Vec<(u64, [bool; 16])>>,
+    frames: Vec<Pixels>,
+    beeping: bool,
+    frame_limit: usize,
+}
+
+impl HeadlessPlatform {
+    pub fn new(frame_limit: usize) -> HeadlessPlatform {
+        HeadlessPlatform {
+            input: [false; 16],
+            recording: None,
+            frames: Vec::new(),
+            beeping: false,
+            frame_limit,
+        }
+    }
+
+    /// Feeds the emulator input recorded with `Emulator::set_input_recorder`
+    /// instead of the input set with `set_input`.
+    pub fn replaying(recording: Vec<(u64, [bool; 16])>, frame_limit: usize) -> HeadlessPlatform {
+        HeadlessPlatform {
+            recording: Some(recording),
+            ..HeadlessPlatform::new(frame_limit)
+        }
+    }
+
+    pub fn set_input(&mut self, input: [bool; 16]) {
+        self.input = input;
+    }
+
+    pub fn frames(&self) -> &[Pixels] {
+        &self.frames
+    }
+
+    pub fn is_beeping(&self) -> bool {
+        self.beeping
+    }
+
+    pub fn run(&mut self, emulator: &mut Emulator) {
+        if let Some(recording) = &self.recording {
+            emulator.replay_input(recording.clone());
+        }
+        while !self.should_close() {
+            if self.recording.is_none() {
+                emulator.input = self.poll_input();
+            }
+
+            let mut total_update_time = Duration::ZERO;
+            while total_update_time < FRAME_TIME {
+                emulator.step(STEP_TIME);
+                total_update_time += STEP_TIME;
+            }
+            if self.recording.is_some() {
+                self.input = emulator.input;
+            }
+
+            self.beep(emulator.is_beeping());
+            self.present(&emulator.active_pixels);
+        }
+    }
+}
+
+impl Platform for HeadlessPlatform {
+    /// The input set with `set_input`, or the one most recently replayed into the
+    /// emulator.
+    fn poll_input(&mut self) -> [bool; 16] {
+        self.input
+    }
+
+    fn present(&mut self, active_pixels: &Pixels) {
+        self.frames.push(active_pixels.clone());
+    }
+
+    fn beep(&mut self, on: bool) {
+        self.beeping = on;
+    }
+
+    fn should_close(&self) -> bool {
+        self.frames.len() >= self.frame_limit
+    }
+}
+
+/// Parses the `<cycle> <keypad>` lines written by `Emulator::set_input_recorder`.
+pub fn read_input_recording<R: BufRead>(reader: R) -> Result<Vec<(u64, [bool; 16])>, Chip8Error> {
+    let mut recording = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let invalid_line = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid input recording line '{}'", line),
+            )
+        };
+        let (cycle, keypad) = line.split_once(' ').ok_or_else(invalid_line)?;
+        let cycle = cycle.parse().map_err(|_| invalid_line())?;
+        let keypad = u16::from_str_radix(keypad, 16).map_err(|_| invalid_line())?;
+        let input = std::array::from_fn(|key| keypad & (1 << key) != 0);
+        recording.push((cycle, input));
+    }
+    Ok(recording)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_record_frames_without_window() {
+        // Given
+        let mut emulator = Emulator::new();
+        emulator.load_program_from_data(&[
+            0x00, 0xE0, // CLS
+            0x60, 0x0F, // LD V0, 0x0F
+            0xF0, 0x29, // LD F, V0
+            0xD2, 0x2A, // DRW V2, V2, 0xA
+            0x12, 0x08, // JP 0x208
+        ]).unwrap();
+        let mut platform = HeadlessPlatform::new(3);
+
+        // When
+        platform.run(&mut emulator);
+
+        // Then
+        assert_eq!(platform.frames().len(), 3);
+        assert_eq!(platform.frames()[2], emulator.active_pixels);
+        assert!(platform.frames()[2].contains(&(2, 2)));
+        assert!(!platform.is_beeping());
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(data)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn should_replay_recorded_input() {
+        // Given
+        let program = [
+            0xF0, 0x0A, // LD V0, K
+            0x81, 0x04, // ADD V1, V0
+            0xF0, 0x29, // LD F, V0
+            0x00, 0xE0, // CLS
+            0xD2, 0x25, // DRW V2, V2, 0x5
+            0x12, 0x00, // JP 0x200
+        ];
+        let mut recorded = Emulator::new();
+        recorded.load_program_from_data(&program).unwrap();
+        let buffer = SharedBuffer::default();
+        recorded.set_input_recorder(buffer.clone());
+        for keypad in [0x0000, 0x0020, 0x0020, 0x0000, 0x0100, 0x0000, 0x0000] {
+            recorded.set_keypad(keypad);
+            for _ in 0..16 {
+                recorded.step(STEP_TIME);
+            }
+        }
+        let recording = read_input_recording(buffer.0.borrow().as_slice()).unwrap();
+
+        // When
+        let mut replayed = Emulator::new();
+        replayed.load_program_from_data(&program).unwrap();
+        let mut platform = HeadlessPlatform::replaying(recording.clone(), 7);
+        platform.run(&mut replayed);
+
+        // Then
+        assert_eq!(recording.len(), 4);
+        assert_eq!(replayed.cycle_count(), recorded.cycle_count());
+        assert_eq!(replayed.cpu.registers, recorded.cpu.registers);
+        assert_eq!(replayed.cpu.registers[0x1], 0x5 + 0x8);
+        assert_eq!(platform.frames()[6], recorded.active_pixels);
+    }
+
+    #[test]
+    fn should_fail_to_read_invalid_input_recording() {
+        let result = read_input_recording("12 0010\n34 zz\n".as_bytes());
+
+        assert!(matches!(result, Err(Chip8Error::Io(_))));
+    }
+}
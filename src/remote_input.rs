@@ -0,0 +1,174 @@
+//! Keypad input fed from outside the emulator process.
+//!
+//! Clients send the keypad state as 16-bit big-endian bitmasks, where bit `k`
+//! set means CHIP-8 key `k` is held down. Every complete mask replaces the
+//! previous state.
+
+#[cfg(unix)]
+use std::io::{self, ErrorKind, Read};
+#[cfg(unix)]
+use std::os::unix::{
+    fs::FileTypeExt,
+    net::{UnixListener, UnixStream},
+};
+#[cfg(unix)]
+use std::{fs, path::Path, path::PathBuf};
+
+/// Reassembles keypad bitmasks from an arbitrarily chunked byte stream.
+#[derive(Default)]
+pub struct KeypadStream {
+    pending_byte: Option<u8>,
+}
+
+impl KeypadStream {
+    pub fn new() -> KeypadStream {
+        KeypadStream { pending_byte: None }
+    }
+
+    /// Consumes `bytes` and returns the last complete bitmask they finished, if any.
+    pub fn feed(&mut self, bytes: &[u8]) -> Option<u16> {
+        let mut keypad = None;
+        for byte in bytes {
+            match self.pending_byte.take() {
+                Some(high_byte) => keypad = Some(u16::from_be_bytes([high_byte, *byte])),
+                None => self.pending_byte = Some(*byte),
+            }
+        }
+        keypad
+    }
+}
+
+/// Listens on a Unix domain socket and tracks the keypad state sent by its clients.
+#[cfg(unix)]
+pub struct RemoteKeypad {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<(UnixStream, KeypadStream)>,
+    keypad: u16,
+}
+
+#[cfg(unix)]
+impl RemoteKeypad {
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<RemoteKeypad> {
+        let path = path.as_ref().to_path_buf();
+        // NOTE(panmar): A socket left behind by a previous run would make bind fail
+        if let Ok(metadata) = fs::symlink_metadata(&path) {
+            if metadata.file_type().is_socket() {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(RemoteKeypad {
+            path,
+            listener,
+            clients: Vec::new(),
+            keypad: 0,
+        })
+    }
+
+    /// Accepts pending clients, drains their input without blocking and returns
+    /// the current keypad bitmask. All keys are released when a client disconnects.
+    pub fn poll(&mut self) -> u16 {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push((stream, KeypadStream::new()));
+            }
+        }
+
+        let mut buffer = [0u8; 64];
+        let mut disconnected = false;
+        self.clients.retain_mut(|(stream, keypad_stream)| loop {
+            match stream.read(&mut buffer) {
+                Ok(0) => {
+                    disconnected = true;
+                    return false;
+                }
+                Ok(n) => {
+                    if let Some(keypad) = keypad_stream.feed(&buffer[..n]) {
+                        self.keypad = keypad;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => {
+                    disconnected = true;
+                    return false;
+                }
+            }
+        });
+
+        if disconnected {
+            self.keypad = 0;
+        }
+        self.keypad
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RemoteKeypad {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_keypad_bitmasks_from_chunked_bytes() {
+        // Given
+        let mut keypad_stream = KeypadStream::new();
+
+        // When
+        let first = keypad_stream.feed(&[0x80]);
+        let second = keypad_stream.feed(&[0x01, 0x00, 0x12, 0x40]);
+        let third = keypad_stream.feed(&[0x02]);
+
+        // Then
+        assert_eq!(first, None);
+        assert_eq!(second, Some(0x0012));
+        assert_eq!(third, Some(0x4002));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_read_keypad_from_unix_socket() {
+        use std::io::Write;
+        use std::time::Duration;
+
+        // Given
+        let path = std::env::temp_dir().join(format!("chip8-keypad-{}.sock", std::process::id()));
+        let mut remote_keypad = RemoteKeypad::bind(&path).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+
+        // When
+        client.write_all(&[0x00, 0x01, 0x80, 0x20]).unwrap();
+        let mut keypad = 0;
+        for _ in 0..100 {
+            keypad = remote_keypad.poll();
+            if keypad != 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        // Then
+        assert_eq!(keypad, 0x8020);
+
+        // When
+        drop(client);
+        for _ in 0..100 {
+            keypad = remote_keypad.poll();
+            if keypad == 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        // Then
+        assert_eq!(keypad, 0);
+    }
+}
@@ -3,32 +3,107 @@
 
 extern crate sdl2;
 
-use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::chip8::{Emulator, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::chip8::{
+    palette_index, Emulator, Pixels, QuirkPreset, DEFAULT_RGBA_PALETTE, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+use crate::platform::Platform;
+#[cfg(unix)]
+use crate::remote_input::RemoteKeypad;
 use sdl2::{
     audio::{AudioCallback, AudioDevice, AudioSpecDesired},
     event::Event,
-    keyboard::Keycode,
+    keyboard::{Keycode, Scancode},
     pixels::Color,
     rect::Rect,
     render::Canvas,
     video::Window,
-    Sdl,
+    EventPump, Sdl,
 };
-use std::collections::HashSet;
+use std::collections::HashMap;
+
+/// Pixels that are on, white by default.
+pub const DEFAULT_FG_COLOR: Color = rgba_color(DEFAULT_RGBA_PALETTE[1]);
+/// Pixels that are off, black by default.
+pub const DEFAULT_BG_COLOR: Color = rgba_color(DEFAULT_RGBA_PALETTE[0]);
+/// `DEFAULT_RGBA_PALETTE` as SDL colors.
+pub const DEFAULT_PALETTE: [Color; 4] = [
+    DEFAULT_BG_COLOR,
+    DEFAULT_FG_COLOR,
+    rgba_color(DEFAULT_RGBA_PALETTE[2]),
+    rgba_color(DEFAULT_RGBA_PALETTE[3]),
+];
+
+const fn rgba_color([r, g, b, a]: [u8; 4]) -> Color {
+    Color::RGBA(r, g, b, a)
+}
+
+/// Frequency of the beep played when no XO-CHIP audio pattern is loaded.
+pub const DEFAULT_BEEP_FREQUENCY: f32 = 440.0;
+
+/// Master volume of the beep, between 0.0 and 1.0.
+pub const DEFAULT_VOLUME: f32 = 0.25;
+
+/// Frames presented per second unless changed with `set_target_fps`.
+pub const DEFAULT_TARGET_FPS: u32 = 60;
+
+const MIN_SPEED_MULTIPLIER: f32 = 0.125;
+const MAX_SPEED_MULTIPLIER: f32 = 8.0;
+/// Applied on top of the speed multiplier while the turbo key is held.
+const TURBO_MULTIPLIER: f32 = 8.0;
+/// Samples the beep takes to fade in or out, about 1.5 ms at 44.1 kHz.
+const ENVELOPE_SAMPLES: f32 = 64.0;
+
+/// Shape of the beep played when no XO-CHIP audio pattern is loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    /// Softer than the square wave.
+    Sine,
+    Triangle,
+}
 
 pub struct SDLPlatform {
-    context: Sdl,
+    // NOTE(panmar): Unused, but SDL shuts down once the context is dropped
+    _context: Sdl,
     canvas: Canvas<Window>,
+    event_pump: EventPump,
     pending_close: bool,
-    audio: AudioDevice<SquareWave>,
+    pending_quirk_cycle: bool,
+    pending_screenshot: bool,
+    pending_rom: Option<PathBuf>,
+    playlist: Vec<PathBuf>,
+    playlist_index: usize,
+    pending_playlist_step: isize,
+    pending_reload: bool,
+    paused: bool,
+    speed_multiplier: f32,
+    turbo: bool,
+    target_fps: u32,
+    key_map: HashMap<Keycode, usize>,
+    palette: [Color; 4],
+    scale: u32,
+    padding: u32,
+    audio: AudioDevice<Tone>,
+    #[cfg(unix)]
+    remote_keypad: Option<RemoteKeypad>,
 }
 
-struct SquareWave {
+struct Tone {
+    waveform: Waveform,
     phase_inc: f32,
     phase: f32,
     volume: f32,
+    muted: bool,
+    /// Whether the beep should sound, `envelope` following it over a few samples.
+    playing: bool,
+    envelope: f32,
+    sample_rate: f32,
+    pattern: Option<[u8; 16]>,
+    pattern_position: f32,
+    pattern_position_inc: f32,
 }
 
 struct Timer {
@@ -49,23 +124,84 @@ impl Timer {
     }
 }
 
-impl AudioCallback for SquareWave {
+impl Tone {
+    fn new(sample_rate: f32) -> Tone {
+        Tone {
+            waveform: Waveform::Square,
+            phase_inc: phase_increment(DEFAULT_BEEP_FREQUENCY, sample_rate),
+            phase: 0.0,
+            volume: DEFAULT_VOLUME,
+            muted: false,
+            playing: false,
+            envelope: 0.0,
+            sample_rate,
+            pattern: None,
+            pattern_position: 0.0,
+            pattern_position_inc: 0.0,
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        self.envelope = envelope_step(self.envelope, self.playing);
+        // NOTE(panmar): Restarting from the beginning of the wave once silent makes
+        // every beep start the same way
+        if self.envelope == 0.0 {
+            self.phase = 0.0;
+            self.pattern_position = 0.0;
+            return 0.0;
+        }
+        let sample = match &self.pattern {
+            Some(pattern) => {
+                let high = pattern_bit(pattern, self.pattern_position as usize);
+                self.pattern_position = (self.pattern_position + self.pattern_position_inc) % 128.0;
+                if high {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            None => waveform_sample(self.waveform, self.phase),
+        };
+        self.phase = (self.phase + self.phase_inc) % 1.0;
+        if self.muted {
+            0.0
+        } else {
+            sample * self.volume * self.envelope
+        }
+    }
+}
+
+impl AudioCallback for Tone {
     type Channel = f32;
 
     fn callback(&mut self, out: &mut [f32]) {
         for x in out.iter_mut() {
-            if self.phase >= 0.0 && self.phase < 0.5 {
-                *x = self.volume;
-            } else {
-                *x = -self.volume;
-            }
-            self.phase = (self.phase + self.phase_inc) % 1.0;
+            *x = self.next_sample();
         }
     }
 }
 
 impl SDLPlatform {
     pub fn new() -> SDLPlatform {
+        SDLPlatform::with_scale(20, 2)
+    }
+
+    pub fn with_key_map(key_map: HashMap<Keycode, usize>) -> SDLPlatform {
+        let mut platform = SDLPlatform::new();
+        platform.set_key_map(key_map);
+        platform
+    }
+
+    /// Each CHIP-8 pixel becomes a `scale`-sized square, with a `padding` gap on
+    /// every side. Zero padding gives a gapless display.
+    pub fn with_scale(scale: u32, padding: u32) -> SDLPlatform {
+        assert!(
+            padding * 2 < scale,
+            "padding {} leaves nothing of a {} pixel square",
+            padding,
+            scale
+        );
+
         let context = sdl2::init().unwrap();
         let video = context.video().unwrap();
         let audio = context.audio().unwrap();
@@ -77,120 +213,702 @@ impl SDLPlatform {
         };
 
         let audio_device = audio
-            .open_playback(None, &desired_spec, |spec| SquareWave {
-                phase_inc: 440.0 / spec.freq as f32,
-                phase: 0.0,
-                volume: 0.25,
-            })
+            .open_playback(None, &desired_spec, |spec| Tone::new(spec.freq as f32))
             .unwrap();
+        // NOTE(panmar): The device keeps running, `beep` fades the tone in and out
+        // instead, as pausing it mid-wave clicks
+        audio_device.resume();
 
         let window = video
-            .window("CHIP-8 emulator", SCREEN_WIDTH * 20, SCREEN_HEIGHT * 20)
+            .window(
+                "CHIP-8 emulator",
+                SCREEN_WIDTH * scale,
+                SCREEN_HEIGHT * scale,
+            )
             .position_centered()
             .build()
             .unwrap();
         let canvas = window.into_canvas().build().unwrap();
+        let event_pump = context.event_pump().unwrap();
 
         SDLPlatform {
-            context,
+            _context: context,
             canvas,
+            event_pump,
             pending_close: false,
+            pending_quirk_cycle: false,
+            pending_screenshot: false,
+            pending_rom: None,
+            playlist: Vec::new(),
+            playlist_index: 0,
+            pending_playlist_step: 0,
+            pending_reload: false,
+            paused: false,
+            speed_multiplier: 1.0,
+            turbo: false,
+            target_fps: DEFAULT_TARGET_FPS,
+            key_map: default_key_map(),
+            palette: DEFAULT_PALETTE,
+            scale,
+            padding,
             audio: audio_device,
+            #[cfg(unix)]
+            remote_keypad: None,
         }
     }
 
+    pub fn with_colors(fg_color: Color, bg_color: Color) -> SDLPlatform {
+        let mut platform = SDLPlatform::new();
+        platform.set_fg_color(fg_color);
+        platform.set_bg_color(bg_color);
+        platform
+    }
+
+    pub fn set_fg_color(&mut self, color: Color) {
+        self.palette[1] = color;
+    }
+
+    pub fn set_bg_color(&mut self, color: Color) {
+        self.palette[0] = color;
+    }
+
+    pub fn set_palette(&mut self, palette: [Color; 4]) {
+        self.palette = palette;
+    }
+
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.audio.lock().waveform = waveform;
+    }
+
+    /// Sets the pitch of the beep. Panics unless `hz` is positive and below half the
+    /// audio sample rate.
+    pub fn set_beep_frequency(&mut self, hz: f32) {
+        let mut tone = self.audio.lock();
+        let nyquist_frequency = tone.sample_rate / 2.0;
+        assert!(
+            hz > 0.0 && hz < nyquist_frequency,
+            "beep frequency {} Hz outside of 0..{} Hz",
+            hz,
+            nyquist_frequency
+        );
+        tone.phase_inc = phase_increment(hz, tone.sample_rate);
+    }
+
+    /// Sets the master volume, clamped to 0.0..=1.0.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.audio.lock().volume = clamp_volume(volume);
+    }
+
+    /// Silences the beep while keeping the audio device running.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.audio.lock().muted = muted;
+    }
+
+    /// Panics if any key maps past CHIP-8 key 0xF, like `remap_key`.
+    pub fn set_key_map(&mut self, key_map: HashMap<Keycode, usize>) {
+        assert_key_map(&key_map);
+        self.key_map = key_map;
+    }
+
+    /// Makes `keycode` press CHIP-8 key `chip8_key`, in addition to its other keys.
+    pub fn remap_key(&mut self, keycode: Keycode, chip8_key: usize) {
+        assert!(chip8_key < 16, "CHIP-8 key {:#X} out of range", chip8_key);
+        self.key_map.insert(keycode, chip8_key);
+    }
+
+    /// Merges keys received by `remote_keypad` with the keyboard state on every frame.
+    #[cfg(unix)]
+    pub fn set_remote_keypad(&mut self, remote_keypad: RemoteKeypad) {
+        self.remote_keypad = Some(remote_keypad);
+    }
+
+    /// Caps how often the display is presented. The CPU clock does not depend on it.
+    pub fn set_target_fps(&mut self, target_fps: u32) {
+        assert!(target_fps > 0, "target FPS must be positive");
+        self.target_fps = target_fps;
+    }
+
+    /// Runs the ROMs of `playlist` one at a time, starting with the first. PageDown
+    /// switches to the next ROM and PageUp to the previous one, wrapping around.
+    pub fn run_playlist(&mut self, emulator: &mut Emulator, playlist: Vec<PathBuf>) {
+        self.pending_rom = playlist.first().cloned();
+        self.playlist = playlist;
+        self.playlist_index = 0;
+        self.run(emulator);
+    }
+
+    fn step_playlist(&mut self, offset: isize) {
+        if self.playlist.is_empty() {
+            return;
+        }
+        self.playlist_index = playlist_index(self.playlist_index, self.playlist.len(), offset);
+        self.pending_rom = Some(self.playlist[self.playlist_index].clone());
+    }
+
     pub fn run(&mut self, emulator: &mut Emulator) {
         let mut update_timer = Timer::new();
-        while !self.pending_close {
+        let mut frame_timer = Timer::new();
+        while !self.should_close() {
             self.update(emulator, &mut update_timer);
-            self.draw(emulator);
+            self.present_planes(&emulator.active_pixels, &emulator.second_plane);
+
+            std::thread::sleep(frame_sleep_duration(
+                frame_timer.timer.elapsed(),
+                self.target_fps,
+            ));
+            frame_timer.tick();
         }
     }
 
     fn update(&mut self, emulator: &mut Emulator, timer: &mut Timer) {
-        self.update_input(emulator);
+        emulator.input = self.poll_input();
+        if self.pending_quirk_cycle {
+            self.pending_quirk_cycle = false;
+            self.cycle_quirk_preset(emulator);
+        }
+        if self.pending_screenshot {
+            self.pending_screenshot = false;
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let path = format!("screenshot-{}.png", seconds);
+            if let Err(e) = self.save_screenshot(emulator, &path) {
+                eprintln!("Failed to save screenshot {}: {}", path, e);
+            }
+        }
+
+        if self.pending_playlist_step != 0 {
+            let offset = std::mem::take(&mut self.pending_playlist_step);
+            self.step_playlist(offset);
+        }
+        if let Some(path) = self.pending_rom.take() {
+            if let Err(e) = emulator.reset_and_load_program_from_file(&path) {
+                eprintln!("Failed to load {}: {}", path.display(), e);
+            }
+        }
+        if self.pending_reload {
+            self.pending_reload = false;
+            emulator.reset_and_reload();
+        }
+
+        let elapsed_time = timer.tick();
+        if self.paused {
+            self.beep(false);
+            return;
+        }
+
+        let elapsed_time = scale_elapsed_time(
+            elapsed_time,
+            effective_speed_multiplier(self.speed_multiplier, self.turbo),
+        );
+        if let Err(e) = emulator.try_step(elapsed_time) {
+            self.canvas
+                .window_mut()
+                .set_title(&format!("CHIP-8 emulator [halted: {}]", e))
+                .unwrap();
+        }
+        self.beep(emulator.is_beeping());
+        self.set_sound_pattern(emulator.sound_pattern().copied(), emulator.playback_rate());
+    }
+
+    /// Plays `pattern` bits at `playback_rate` per second instead of the square wave.
+    fn set_sound_pattern(&mut self, pattern: Option<[u8; 16]>, playback_rate: f32) {
+        let mut tone = self.audio.lock();
+        if tone.pattern != pattern {
+            tone.pattern = pattern;
+            tone.pattern_position = 0.0;
+        }
+        tone.pattern_position_inc = playback_rate / tone.sample_rate;
+    }
+
+    /// Writes the display to a PNG in the window's colors, one CHIP-8 pixel per
+    /// `scale` image pixels, or half that in high resolution.
+    pub fn save_screenshot<P: AsRef<Path>>(
+        &self,
+        emulator: &Emulator,
+        path: P,
+    ) -> Result<(), image::ImageError> {
+        let scale = self.scale * SCREEN_WIDTH / emulator.active_pixels.width();
+        let palette = self.palette.map(|color| {
+            let (r, g, b, a) = color.rgba();
+            [r, g, b, a]
+        });
+        image::save_buffer(
+            path,
+            &emulator.to_rgba_with_palette(scale, &palette),
+            emulator.active_pixels.width() * scale,
+            emulator.active_pixels.height() * scale,
+            image::ColorType::Rgba8,
+        )
+    }
+
+    fn cycle_quirk_preset(&mut self, emulator: &mut Emulator) {
+        let preset = match emulator.quirk_preset() {
+            Some(preset) => preset.next(),
+            None => QuirkPreset::ALL[0],
+        };
+        emulator.set_quirk_preset(preset);
+        // NOTE(panmar): Resizing memory pulls it from under the running program, so
+        // it starts over instead
+        if emulator.has_wide_memory() != preset.uses_wide_memory() {
+            emulator.set_wide_memory(preset.uses_wide_memory());
+            emulator.reset_and_reload();
+        }
+        self.canvas
+            .window_mut()
+            .set_title(&format!("CHIP-8 emulator [{}]", preset.name()))
+            .unwrap();
+    }
+}
+
+// NOTE(panmar): Use more convenient QWERTY keyboard mapping
+// 1 2 3 C                 1 2 3 4
+// 4 5 6 D      ====>      Q W E R
+// 7 8 9 E      ====>      A S D F
+// A 0 B F                 Z X C V
+const QWERTY_KEYS: [(Keycode, usize); 16] = [
+    (Keycode::Num1, 0x1),
+    (Keycode::Num2, 0x2),
+    (Keycode::Num3, 0x3),
+    (Keycode::Num4, 0xC),
+    (Keycode::Q, 0x4),
+    (Keycode::W, 0x5),
+    (Keycode::E, 0x6),
+    (Keycode::R, 0xD),
+    (Keycode::A, 0x7),
+    (Keycode::S, 0x8),
+    (Keycode::D, 0x9),
+    (Keycode::F, 0xE),
+    (Keycode::Z, 0xA),
+    (Keycode::X, 0x0),
+    (Keycode::C, 0xB),
+    (Keycode::V, 0xF),
+];
+
+pub fn default_key_map() -> HashMap<Keycode, usize> {
+    HashMap::from(QWERTY_KEYS)
+}
+
+/// The CHIP-8 key `keycode` stands for in the default QWERTY mapping.
+pub fn map_keycode(keycode: Keycode) -> Option<usize> {
+    QWERTY_KEYS
+        .iter()
+        .find(|(key, _)| *key == keycode)
+        .map(|(_, chip8_key)| *chip8_key)
+}
+
+/// Index `offset` entries away from `index` in a playlist of `len` ROMs, wrapping
+/// around at both ends.
+fn playlist_index(index: usize, len: usize, offset: isize) -> usize {
+    (index as isize + offset).rem_euclid(len as isize) as usize
+}
+
+/// How long to sleep after a frame that took `frame_time` to keep to `target_fps`.
+fn frame_sleep_duration(frame_time: Duration, target_fps: u32) -> Duration {
+    (Duration::from_secs(1) / target_fps).saturating_sub(frame_time)
+}
+
+/// Turbo speeds up emulated time as a whole, so timers and sound keep pace with the CPU.
+fn effective_speed_multiplier(speed_multiplier: f32, turbo: bool) -> f32 {
+    if turbo {
+        speed_multiplier * TURBO_MULTIPLIER
+    } else {
+        speed_multiplier
+    }
+}
+
+/// Emulated time passing in `elapsed_time` of real time at `speed_multiplier`.
+fn scale_elapsed_time(elapsed_time: Duration, speed_multiplier: f32) -> Duration {
+    Duration::from_nanos((elapsed_time.as_nanos() as f64 * speed_multiplier as f64) as u64)
+}
 
-        let mut total_update_time = Duration::ZERO;
-        while total_update_time < Duration::from_millis(16) {
-            let elapsed_time = timer.tick();
-            emulator.step(elapsed_time);
+/// Where pixel (`x`, `y`) is drawn in the window.
+fn pixel_rect(x: u32, y: u32, scale: u32, padding: u32) -> Rect {
+    Rect::new(
+        (x * scale + padding) as i32,
+        (y * scale + padding) as i32,
+        scale - 2 * padding,
+        scale - 2 * padding,
+    )
+}
 
-            if emulator.cpu.sound_timer > 0 {
-                self.audio.resume();
+/// One sample in -1.0..=1.0 of `waveform` at `phase`, the fraction of its period
+/// already played.
+fn waveform_sample(waveform: Waveform, phase: f32) -> f32 {
+    match waveform {
+        Waveform::Square => {
+            if phase < 0.5 {
+                1.0
             } else {
-                self.audio.pause();
+                -1.0
             }
+        }
+        Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+        Waveform::Triangle => 1.0 - 4.0 * ((phase + 0.25).rem_euclid(1.0) - 0.5).abs(),
+    }
+}
+
+/// The envelope one sample later, ramping linearly towards 1.0 while `playing` and
+/// towards 0.0 otherwise.
+fn envelope_step(envelope: f32, playing: bool) -> f32 {
+    if playing {
+        (envelope + 1.0 / ENVELOPE_SAMPLES).min(1.0)
+    } else {
+        (envelope - 1.0 / ENVELOPE_SAMPLES).max(0.0)
+    }
+}
+
+/// Fraction of a period a tone of `frequency` advances per sample.
+fn phase_increment(frequency: f32, sample_rate: f32) -> f32 {
+    frequency / sample_rate
+}
+
+fn clamp_volume(volume: f32) -> f32 {
+    if volume.is_nan() {
+        0.0
+    } else {
+        volume.clamp(0.0, 1.0)
+    }
+}
+
+/// Bit `position` of the 128-bit XO-CHIP audio pattern, most significant bit first.
+fn pattern_bit(pattern: &[u8; 16], position: usize) -> bool {
+    pattern[position / 8] & (0x80 >> (position % 8)) != 0
+}
+
+fn assert_key_map(key_map: &HashMap<Keycode, usize>) {
+    for &chip8_key in key_map.values() {
+        assert!(chip8_key < 16, "CHIP-8 key {:#X} out of range", chip8_key);
+    }
+}
 
-            total_update_time += elapsed_time;
+/// The CHIP-8 keypad state for the pressed keyboard keys.
+fn map_keys<I: IntoIterator<Item = Keycode>>(
+    key_map: &HashMap<Keycode, usize>,
+    pressed: I,
+) -> [bool; 16] {
+    let mut input = [false; 16];
+    for keycode in pressed {
+        if let Some(chip8_key) = key_map.get(&keycode) {
+            input[*chip8_key] = true;
         }
     }
+    input
+}
 
-    // NOTE(panmar): Use more convenient QWERTY keyboard mapping
-    // 1 2 3 C                 1 2 3 4
-    // 4 5 6 D      ====>      Q W E R
-    // 7 8 9 E      ====>      A S D F
-    // A 0 B F                 Z X C V
-    fn update_input(&mut self, emulator: &mut Emulator) {
-        let mut event_pump = self.context.event_pump().unwrap();
-        for event in event_pump.poll_iter() {
+impl Platform for SDLPlatform {
+    fn poll_input(&mut self) -> [bool; 16] {
+        for event in self.event_pump.poll_iter() {
             match event {
                 Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
                 } => self.pending_close = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F7),
+                    repeat: false,
+                    ..
+                } => self.pending_quirk_cycle = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Space),
+                    repeat: false,
+                    ..
+                } => self.paused = !self.paused,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Equals | Keycode::Plus | Keycode::KpPlus),
+                    ..
+                } => {
+                    self.speed_multiplier = (self.speed_multiplier * 2.0).min(MAX_SPEED_MULTIPLIER)
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Minus | Keycode::KpMinus),
+                    ..
+                } => {
+                    self.speed_multiplier = (self.speed_multiplier / 2.0).max(MIN_SPEED_MULTIPLIER)
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    repeat: false,
+                    ..
+                } => self.pending_screenshot = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F5),
+                    repeat: false,
+                    ..
+                } => self.pending_reload = true,
+                Event::DropFile { filename, .. } => self.pending_rom = Some(filename.into()),
+                Event::KeyDown {
+                    keycode: Some(Keycode::PageDown),
+                    repeat: false,
+                    ..
+                } => self.pending_playlist_step += 1,
+                Event::KeyDown {
+                    keycode: Some(Keycode::PageUp),
+                    repeat: false,
+                    ..
+                } => self.pending_playlist_step -= 1,
                 _ => {}
             }
         }
-        let pressed_keys: HashSet<Keycode> = event_pump
-            .keyboard_state()
+        let keyboard_state = self.event_pump.keyboard_state();
+        self.turbo = keyboard_state.is_scancode_pressed(Scancode::Tab);
+        let pressed_keys = keyboard_state
             .pressed_scancodes()
-            .filter_map(Keycode::from_scancode)
-            .collect();
-
-        emulator.input.fill(false);
-        for keycode in pressed_keys {
-            match keycode {
-                Keycode::Num1 => emulator.input[1] = true,
-                Keycode::Num2 => emulator.input[2] = true,
-                Keycode::Num3 => emulator.input[3] = true,
-                Keycode::Q => emulator.input[4] = true,
-                Keycode::W => emulator.input[5] = true,
-                Keycode::E => emulator.input[6] = true,
-                Keycode::A => emulator.input[7] = true,
-                Keycode::S => emulator.input[8] = true,
-                Keycode::D => emulator.input[9] = true,
-                Keycode::Z => emulator.input[0xA] = true,
-                Keycode::X => emulator.input[0] = true,
-                Keycode::C => emulator.input[0xB] = true,
-                Keycode::Num4 => emulator.input[0xC] = true,
-                Keycode::R => emulator.input[0xD] = true,
-                Keycode::F => emulator.input[0xE] = true,
-                Keycode::V => emulator.input[0xF] = true,
-                _ => {}
-            };
+            .filter_map(Keycode::from_scancode);
+        let mut input = map_keys(&self.key_map, pressed_keys);
+
+        #[cfg(unix)]
+        if let Some(remote_keypad) = &mut self.remote_keypad {
+            let keypad = remote_keypad.poll();
+            for (key, pressed) in input.iter_mut().enumerate() {
+                *pressed |= keypad & (1 << key) != 0;
+            }
         }
+
+        input
     }
 
-    fn draw(&mut self, emulator: &Emulator) {
-        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
-        self.canvas.clear();
+    fn present(&mut self, active_pixels: &Pixels) {
+        let second_plane = Pixels::with_resolution(active_pixels.resolution());
+        self.present_planes(active_pixels, &second_plane);
+    }
 
-        self.canvas.set_draw_color(Color::RGB(255, 255, 255));
-        let pixel_size = 20u32;
+    fn present_planes(&mut self, first_plane: &Pixels, second_plane: &Pixels) {
+        self.canvas.set_draw_color(self.palette[0]);
+        self.canvas.clear();
 
-        let padding = 2;
-        for pixel in emulator.active_pixels.iter() {
+        // NOTE(panmar): The window keeps its size, high resolution pixels are half as big
+        let pixels_per_lores_pixel = first_plane.width() / SCREEN_WIDTH;
+        let scale = self.scale / pixels_per_lores_pixel;
+        let padding = self.padding / pixels_per_lores_pixel;
+        for (x, y) in first_plane.iter().chain(second_plane.iter()) {
+            let color = palette_index(first_plane, second_plane, (x, y));
+            // NOTE(panmar): Pixels on both planes come up twice, drawn the same both times
+            self.canvas.set_draw_color(self.palette[color]);
             self.canvas
-                .fill_rect(Rect::new(
-                    pixel_size as i32 * pixel.0 as i32,
-                    pixel_size as i32 * pixel.1 as i32,
-                    pixel_size - 2 * padding,
-                    pixel_size - 2 * padding,
-                ))
+                .fill_rect(pixel_rect(x, y, scale, padding))
                 .unwrap();
         }
 
         self.canvas.present();
     }
+
+    fn beep(&mut self, on: bool) {
+        self.audio.lock().playing = on;
+    }
+
+    fn should_close(&self) -> bool {
+        self.pending_close
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_map_qwerty_keycodes_to_chip8_keys() {
+        assert_eq!(map_keycode(Keycode::Num1), Some(0x1));
+        assert_eq!(map_keycode(Keycode::Num4), Some(0xC));
+        assert_eq!(map_keycode(Keycode::W), Some(0x5));
+        assert_eq!(map_keycode(Keycode::F), Some(0xE));
+        assert_eq!(map_keycode(Keycode::X), Some(0x0));
+        assert_eq!(map_keycode(Keycode::V), Some(0xF));
+        assert_eq!(map_keycode(Keycode::P), None);
+        assert_eq!(map_keycode(Keycode::Escape), None);
+    }
+
+    #[test]
+    fn should_accept_default_key_map() {
+        assert_key_map(&default_key_map());
+    }
+
+    #[test]
+    #[should_panic(expected = "CHIP-8 key 0x10 out of range")]
+    fn should_reject_key_map_past_last_chip8_key() {
+        // Given
+        let mut key_map = default_key_map();
+        key_map.insert(Keycode::Up, 0x10);
+
+        // When
+        assert_key_map(&key_map);
+    }
+
+    #[test]
+    fn should_map_pressed_keys_to_chip8_keypad() {
+        // Given
+        let mut key_map = default_key_map();
+        key_map.insert(Keycode::Up, 0x5);
+
+        // When
+        let input = map_keys(&key_map, [Keycode::X, Keycode::V, Keycode::Up, Keycode::P]);
+
+        // Then
+        let mut expected = [false; 16];
+        expected[0x0] = true;
+        expected[0x5] = true;
+        expected[0xF] = true;
+        assert_eq!(input, expected);
+    }
+
+    #[test]
+    fn should_place_pixel_rect_within_its_padding() {
+        assert_eq!(pixel_rect(0, 0, 20, 2), Rect::new(2, 2, 16, 16));
+        assert_eq!(pixel_rect(63, 31, 20, 2), Rect::new(1262, 622, 16, 16));
+        assert_eq!(pixel_rect(3, 1, 10, 0), Rect::new(30, 10, 10, 10));
+    }
+
+    #[test]
+    fn should_read_audio_pattern_bits_msb_first() {
+        // Given
+        let mut pattern = [0; 16];
+        pattern[0] = 0b1000_0001;
+        pattern[15] = 0b0000_0001;
+
+        // Then
+        assert!(pattern_bit(&pattern, 0));
+        assert!(!pattern_bit(&pattern, 1));
+        assert!(pattern_bit(&pattern, 7));
+        assert!(!pattern_bit(&pattern, 8));
+        assert!(pattern_bit(&pattern, 127));
+    }
+
+    #[test]
+    fn should_generate_waveform_samples() {
+        let quarter_phases = [0.0, 0.25, 0.5, 0.75];
+        let samples = |waveform| quarter_phases.map(|phase| waveform_sample(waveform, phase));
+
+        assert_eq!(samples(Waveform::Square), [1.0, 1.0, -1.0, -1.0]);
+        assert_eq!(samples(Waveform::Triangle), [0.0, 1.0, 0.0, -1.0]);
+        for (sample, expected) in samples(Waveform::Sine)
+            .into_iter()
+            .zip([0.0, 1.0, 0.0, -1.0])
+        {
+            assert!((sample - expected).abs() < 1e-6);
+        }
+        assert_eq!(waveform_sample(Waveform::Triangle, 0.125), 0.5);
+    }
+
+    #[test]
+    fn should_ramp_envelope_in_and_out() {
+        assert_eq!(envelope_step(0.0, true), 1.0 / 64.0);
+        assert_eq!(envelope_step(0.5, true), 0.5 + 1.0 / 64.0);
+        assert_eq!(envelope_step(1.0, true), 1.0);
+        assert_eq!(envelope_step(1.0, false), 1.0 - 1.0 / 64.0);
+        assert_eq!(envelope_step(0.5, false), 0.5 - 1.0 / 64.0);
+        assert_eq!(envelope_step(0.0, false), 0.0);
+    }
+
+    #[test]
+    fn should_fade_beep_in_and_out() {
+        // Given
+        let mut tone = Tone::new(44100.0);
+        tone.volume = 1.0;
+        tone.phase_inc = 0.0;
+
+        // When
+        tone.playing = true;
+        let attack: Vec<f32> = (0..65).map(|_| tone.next_sample()).collect();
+        tone.playing = false;
+        let release: Vec<f32> = (0..65).map(|_| tone.next_sample()).collect();
+
+        // Then
+        assert_eq!(attack[0], 1.0 / 64.0);
+        assert_eq!(attack[31], 0.5);
+        assert_eq!(attack[63], 1.0);
+        assert_eq!(attack[64], 1.0);
+        assert_eq!(release[0], 1.0 - 1.0 / 64.0);
+        assert_eq!(release[31], 0.5);
+        assert_eq!(release[63], 0.0);
+        assert_eq!(release[64], 0.0);
+    }
+
+    #[test]
+    fn should_restart_wave_once_faded_out() {
+        // Given
+        let mut tone = Tone::new(44100.0);
+        tone.playing = true;
+        for _ in 0..100 {
+            tone.next_sample();
+        }
+
+        // When
+        tone.playing = false;
+        for _ in 0..64 {
+            tone.next_sample();
+        }
+
+        // Then
+        assert_eq!(tone.envelope, 0.0);
+        assert_eq!(tone.phase, 0.0);
+    }
+
+    #[test]
+    fn should_clamp_volume() {
+        assert_eq!(clamp_volume(0.5), 0.5);
+        assert_eq!(clamp_volume(-0.1), 0.0);
+        assert_eq!(clamp_volume(3.0), 1.0);
+        assert_eq!(clamp_volume(f32::NAN), 0.0);
+    }
+
+    #[test]
+    fn should_compute_phase_increment_per_sample() {
+        assert_eq!(phase_increment(440.0, 44100.0), 440.0 / 44100.0);
+        assert_eq!(phase_increment(220.5, 44100.0), 0.005);
+        assert_eq!(phase_increment(22050.0, 44100.0), 0.5);
+    }
+
+    #[test]
+    fn should_scale_elapsed_time_by_speed_multiplier() {
+        let elapsed_time = Duration::from_millis(16);
+
+        assert_eq!(scale_elapsed_time(elapsed_time, 1.0), elapsed_time);
+        assert_eq!(
+            scale_elapsed_time(elapsed_time, 2.0),
+            Duration::from_millis(32)
+        );
+        assert_eq!(
+            scale_elapsed_time(elapsed_time, 0.25),
+            Duration::from_millis(4)
+        );
+    }
+
+    #[test]
+    fn should_apply_turbo_on_top_of_speed_multiplier() {
+        let elapsed_time = Duration::from_millis(16);
+
+        assert_eq!(effective_speed_multiplier(1.0, false), 1.0);
+        assert_eq!(effective_speed_multiplier(0.5, true), 4.0);
+        assert_eq!(
+            scale_elapsed_time(elapsed_time, effective_speed_multiplier(2.0, true)),
+            Duration::from_millis(256)
+        );
+    }
+
+    #[test]
+    fn should_step_through_playlist_wrapping_around() {
+        assert_eq!(playlist_index(0, 3, 1), 1);
+        assert_eq!(playlist_index(2, 3, 1), 0);
+        assert_eq!(playlist_index(0, 3, -1), 2);
+        assert_eq!(playlist_index(1, 3, -1), 0);
+        assert_eq!(playlist_index(0, 1, 1), 0);
+    }
+
+    #[test]
+    fn should_sleep_for_remainder_of_frame() {
+        assert_eq!(
+            frame_sleep_duration(Duration::from_millis(4), 50),
+            Duration::from_millis(16)
+        );
+        assert_eq!(
+            frame_sleep_duration(Duration::ZERO, 60),
+            Duration::from_nanos(16_666_666)
+        );
+        assert_eq!(
+            frame_sleep_duration(Duration::from_millis(30), 60),
+            Duration::ZERO
+        );
+    }
 }
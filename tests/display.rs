@@ -1,17 +1,33 @@
 use chip8_emulator::chip8::Emulator;
 use chip8_emulator::sdl_platform::SDLPlatform;
+use sdl2::pixels::Color;
 
 #[test]
 #[rustfmt::skip]
 #[ignore]
 fn should_display_font() {
     let mut emulator = Emulator::new();
-    emulator.load_program_from_data(&vec!{
+    emulator.load_program_from_data(&[
         0x00, 0xE0,
         0x60, 0x0F,
         0xF0, 0x29,
         0xD2, 0x2A,
-    });
+    ]).unwrap();
     let mut platform = SDLPlatform::new();
     platform.run(&mut emulator);
 }
+
+#[test]
+#[rustfmt::skip]
+#[ignore]
+fn should_display_font_in_amber() {
+    let mut emulator = Emulator::new();
+    emulator.load_program_from_data(&[
+        0x00, 0xE0,
+        0x60, 0x0A,
+        0xF0, 0x29,
+        0xD2, 0x2A,
+    ]).unwrap();
+    let mut platform = SDLPlatform::with_colors(Color::RGB(255, 176, 0), Color::RGB(40, 20, 0));
+    platform.run(&mut emulator);
+}